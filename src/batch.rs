@@ -0,0 +1,107 @@
+use crate::format::maze_to_string;
+use crate::maze::{
+    Algorithm, blank_tiles, carve, choose_exit_tile, mark_entrance, remove_n_random_walls,
+};
+use crate::screenshot::{flip_rows, write_png};
+use crate::thumbnail::render_thumbnail;
+
+use macroquad::prelude::*;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Pixels per tile in exported PNGs
+const BATCH_TILE_PX: u16 = 8;
+// Share of the internal walls opened into loops, like a generated game maze
+const BATCH_LOOP_SHARE: f32 = 0.03;
+
+// Write `count` mazes seeded `seed`, `seed + 1`, ... to `dir` as maze_<seed>.txt
+// and maze_<seed>.png, creating `dir` if needed. Existing files are only
+// replaced with `overwrite`, otherwise nothing is written. Returns the files
+pub fn export_batch(
+    dir: &Path,
+    cols: usize,
+    rows: usize,
+    min_solution_len: usize,
+    count: u64,
+    seed: u64,
+    overwrite: bool,
+) -> io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let paths = |seed: u64| {
+        let name = format!("maze_{}", seed);
+        (
+            dir.join(format!("{}.txt", name)),
+            dir.join(format!("{}.png", name)),
+        )
+    };
+    let seeds = seed..seed + count;
+    if !overwrite
+        && let Some(taken) = seeds
+            .clone()
+            .flat_map(|seed| <[PathBuf; 2]>::from(paths(seed)))
+            .find(|path| path.exists())
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists (use --overwrite)", taken.display()),
+        ));
+    }
+
+    let mut written = Vec::new();
+    for seed in seeds {
+        rand::srand(seed);
+        let mut tiles = blank_tiles(cols, rows);
+        carve(&mut tiles, Algorithm::default());
+        let loops = ((cols - 1) * rows + (rows - 1) * cols) as f32 * BATCH_LOOP_SHARE;
+        remove_n_random_walls(&mut tiles, loops as usize);
+        mark_entrance(&mut tiles, (0, 0));
+        choose_exit_tile(&mut tiles, (0, 0), min_solution_len);
+
+        let (text_path, png_path) = paths(seed);
+        std::fs::write(&text_path, maze_to_string(&tiles))?;
+        // Thumbnails are drawn top down, write_png takes rows bottom up
+        write_png(
+            &flip_rows(&render_thumbnail(&tiles, BATCH_TILE_PX)),
+            &png_path,
+        )?;
+        written.push(text_path);
+        written.push(png_path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::MIN_SOLUTION_LEN;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("maze_batch_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn batch_writes_a_text_file_and_a_png_per_maze() {
+        let dir = temp_dir("files");
+        let written = export_batch(&dir, 6, 5, MIN_SOLUTION_LEN, 3, 40, false).unwrap();
+        assert_eq!(written.len(), 6);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 6);
+        for path in &written {
+            assert!(path.exists(), "{} missing", path.display());
+        }
+        // Taken names are refused without overwrite
+        assert!(export_batch(&dir, 6, 5, MIN_SOLUTION_LEN, 1, 41, false).is_err());
+        assert!(export_batch(&dir, 6, 5, MIN_SOLUTION_LEN, 1, 41, true).is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unwritable_png_is_an_error() {
+        let dir = temp_dir("unwritable");
+        // A directory where the PNG should go can't be opened as a file
+        std::fs::create_dir_all(dir.join("maze_7.png")).unwrap();
+        assert!(export_batch(&dir, 4, 4, MIN_SOLUTION_LEN, 1, 7, true).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
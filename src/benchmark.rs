@@ -0,0 +1,177 @@
+use crate::maze::{Algorithm, blank_tiles, carve_with, dead_ends};
+use crate::solver::{compute_distances, open_neighbors};
+
+use macroquad::miniquad::date;
+use macroquad::rand::RandGenerator;
+
+// Random exits averaged into the mean solution length
+const EXIT_SAMPLES: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct AlgoStats {
+    pub algorithm: Algorithm,
+    pub dead_ends: usize,
+    // Tiles with three or four open sides
+    pub junctions: usize,
+    // Mean distance from the top left tile to EXIT_SAMPLES random tiles
+    pub mean_solution_len: f32,
+    pub generation_ms: f64,
+}
+
+// Carve a cols x rows maze with every algorithm, drawing only from `rng` so
+// the game's own RNG is left alone
+pub fn compare_algorithms(cols: usize, rows: usize, rng: &RandGenerator) -> Vec<AlgoStats> {
+    Algorithm::ALL
+        .into_iter()
+        .map(|algorithm| {
+            let mut tiles = blank_tiles(cols, rows);
+            let started = date::now();
+            carve_with(&mut tiles, algorithm, rng);
+            let generation_ms = (date::now() - started) * 1000.0;
+
+            let distances = compute_distances(&tiles, (0, 0));
+            let total: usize = (0..EXIT_SAMPLES)
+                .map(|_| {
+                    let i = rng.gen_range(0, tiles.vec.len());
+                    distances.vec[i].unwrap_or(0)
+                })
+                .sum();
+            AlgoStats {
+                algorithm,
+                dead_ends: dead_ends(&tiles).len(),
+                junctions: (0..tiles.vec.len())
+                    .filter(|&i| open_neighbors(&tiles, tiles.coords(i)).len() >= 3)
+                    .count(),
+                mean_solution_len: total as f32 / EXIT_SAMPLES as f32,
+                generation_ms,
+            }
+        })
+        .collect()
+}
+
+pub fn print_comparison(stats: &[AlgoStats]) {
+    println!(
+        "{:<12} {:>9} {:>9} {:>13} {:>8}",
+        "algorithm", "dead ends", "junctions", "mean solution", "ms"
+    );
+    for row in stats {
+        println!(
+            "{:<12} {:>9} {:>9} {:>13.1} {:>8.2}",
+            format!("{:?}", row.algorithm),
+            row.dead_ends,
+            row.junctions,
+            row.mean_solution_len,
+            row.generation_ms
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{
+        are_connected, generate_with_isolated_region, mark_exit, remove_n_walls_with,
+    };
+    use crate::solver::{PlayError, is_fully_connected, solve_astar, validate_playable};
+
+    // Seeded mazes the solver is checked on, and their size
+    const FUZZ_SEEDS: u64 = 200;
+    const FUZZ_SIZE: usize = 20;
+
+    // Check A* against a BFS on a cols x rows maze carved from `seed` with a tenth
+    // of its walls knocked out: the path has to run from the top left tile to a
+    // random goal, be exactly as long as the BFS distance and only pass open walls
+    fn check_solver(cols: usize, rows: usize, seed: u64) -> Result<(), String> {
+        let rng = RandGenerator::new();
+        rng.srand(seed);
+        let mut tiles = blank_tiles(cols, rows);
+        carve_with(&mut tiles, Algorithm::default(), &rng);
+        remove_n_walls_with(&mut tiles, cols * rows / 10, &rng);
+
+        let start = (0, 0);
+        let goal = (rng.gen_range(0, cols), rng.gen_range(0, rows));
+        let bfs = compute_distances(&tiles, start)
+            .index(goal.0, goal.1)
+            .ok_or_else(|| format!("BFS can't reach {:?}", goal))?;
+        let path =
+            solve_astar(&tiles, start, goal).ok_or_else(|| format!("A* can't reach {:?}", goal))?;
+        if path.first() != Some(&start) || path.last() != Some(&goal) {
+            return Err(format!("path doesn't run from {:?} to {:?}", start, goal));
+        }
+        if path.len() - 1 != bfs {
+            return Err(format!("A* took {} steps, BFS {}", path.len() - 1, bfs));
+        }
+        match path
+            .windows(2)
+            .find(|pair| !are_connected(&tiles, pair[0], pair[1]))
+        {
+            Some(pair) => Err(format!("path jumps from {:?} to {:?}", pair[0], pair[1])),
+            None => Ok(()),
+        }
+    }
+
+    // The validators on a maze with a walled off pocket: it isn't connected and an
+    // exit inside the pocket can't be played
+    fn check_isolated_region(cols: usize, rows: usize) -> Result<(), String> {
+        let mut tiles = generate_with_isolated_region(cols, rows);
+        if is_fully_connected(&tiles) {
+            return Err("the walled off pocket is still connected".to_string());
+        }
+        if compute_distances(&tiles, (0, 0))
+            .index(cols - 1, rows - 1)
+            .is_some()
+        {
+            return Err("the pocket can be reached from the start".to_string());
+        }
+        mark_exit(&mut tiles, (cols - 1, rows - 1));
+        match validate_playable(&tiles) {
+            Err(PlayError::Unreachable { .. }) => Ok(()),
+            other => Err(format!(
+                "validation with the exit in the pocket gave {:?}",
+                other
+            )),
+        }
+    }
+
+    #[test]
+    fn astar_matches_bfs() {
+        for seed in 0..FUZZ_SEEDS {
+            if let Err(err) = check_solver(FUZZ_SIZE, FUZZ_SIZE, seed) {
+                panic!("solver check failed for seed {}: {}", seed, err);
+            }
+        }
+    }
+
+    #[test]
+    fn walled_off_pocket_is_refused() {
+        check_isolated_region(FUZZ_SIZE, FUZZ_SIZE).unwrap();
+    }
+
+    #[test]
+    fn comparison_has_one_filled_row_per_algorithm() {
+        let rng = RandGenerator::new();
+        rng.srand(9);
+        let stats = compare_algorithms(12, 10, &rng);
+        assert_eq!(stats.len(), Algorithm::ALL.len());
+        for (row, algorithm) in stats.iter().zip(Algorithm::ALL) {
+            assert_eq!(row.algorithm, algorithm);
+            assert!(row.dead_ends > 0, "{:?}", row);
+            assert!(row.junctions > 0, "{:?}", row);
+            assert!(row.mean_solution_len > 0.0, "{:?}", row);
+            assert!(row.generation_ms >= 0.0, "{:?}", row);
+        }
+    }
+
+    #[test]
+    fn comparison_depends_only_on_the_given_rng() {
+        let run = |seed| {
+            let rng = RandGenerator::new();
+            rng.srand(seed);
+            compare_algorithms(12, 10, &rng)
+                .into_iter()
+                .map(|row| (row.dead_ends, row.junctions, row.mean_solution_len))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(4), run(4));
+    }
+}
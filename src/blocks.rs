@@ -0,0 +1,125 @@
+use crate::theme::Theme;
+use crate::tile::{Tile, Wall};
+use crate::utils::Vec2d;
+
+use macroquad::prelude::*;
+
+// Wall thickness in the block look, as a share of the tile size
+const BLOCK_WALL_SHARE: f32 = 0.3;
+
+// The maze on a (2 * cols + 1) x (2 * rows + 1) grid, true where a wall block
+// stands. Odd/odd blocks are the tiles, odd/even ones the walls between them
+// and even/even ones the corners, which stay up while any wall touches them
+pub fn block_grid(tiles: &Vec2d<Tile>) -> Vec2d<bool> {
+    let (cols, rows) = (tiles.cols * 2 + 1, tiles.rows * 2 + 1);
+    // Wall on `side` of the tile, sides past the last tile belong to it too
+    let closed = |col: usize, row: usize, side: Wall| {
+        tiles
+            .index(col.min(tiles.cols - 1), row.min(tiles.rows - 1))
+            .walls()
+            .contains(&side)
+    };
+    let vertical = |x: usize, y: usize| match x {
+        0 => closed(0, y / 2, Wall::Left),
+        _ => closed(x / 2 - 1, y / 2, Wall::Right),
+    };
+    let horizontal = |x: usize, y: usize| match y {
+        0 => closed(x / 2, 0, Wall::Top),
+        _ => closed(x / 2, y / 2 - 1, Wall::Bottom),
+    };
+
+    let mut blocks = Vec::with_capacity(cols * rows);
+    for y in 0..rows {
+        for x in 0..cols {
+            blocks.push(match (x % 2, y % 2) {
+                (1, 1) => false,
+                (0, 1) => vertical(x, y),
+                (1, 0) => horizontal(x, y),
+                _ => {
+                    (y > 0 && vertical(x, y - 1))
+                        || (y + 1 < rows && vertical(x, y + 1))
+                        || (x > 0 && horizontal(x - 1, y))
+                        || (x + 1 < cols && horizontal(x + 1, y))
+                }
+            });
+        }
+    }
+    Vec2d::new(blocks, rows, cols)
+}
+
+// Classic look, solid wall blocks on a plain floor instead of the border shader.
+// Walls are centered on the tile edges so players stay on the tile centers
+pub fn draw_block_style(tiles: &Vec2d<Tile>, theme: &Theme) {
+    let Some(first) = tiles.vec.first() else {
+        return;
+    };
+    let origin = first.screen_position;
+    let size = first.width;
+    let half_wall = size * BLOCK_WALL_SHARE / 2.0;
+    // Screen span of block `i` along an axis starting at `start`
+    let span = |i: usize, start: f32| {
+        let edge = start + (i / 2) as f32 * size;
+        if i.is_multiple_of(2) {
+            (edge - half_wall, edge + half_wall)
+        } else {
+            (edge + half_wall, edge + size - half_wall)
+        }
+    };
+
+    for tile in &tiles.vec {
+        let color = if tile.exit {
+            theme.exit_color
+        } else if tile.entrance {
+            theme.entrance_color
+        } else {
+            theme.path_color
+        };
+        draw_rectangle(
+            tile.screen_position.x,
+            tile.screen_position.y,
+            tile.width,
+            tile.height,
+            color,
+        );
+    }
+
+    let blocks = block_grid(tiles);
+    for (i, _) in blocks.vec.iter().enumerate().filter(|(_, wall)| **wall) {
+        let (x, y) = blocks.coords(i);
+        let (x0, x1) = span(x, origin.x);
+        let (y0, y1) = span(y, origin.y);
+        draw_rectangle(x0, y0, x1 - x0, y1 - y0, theme.wall_color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{blank_tiles, remove_walls_between_positions};
+
+    #[test]
+    fn block_grid_has_a_block_per_tile_wall_and_corner() {
+        let blocks = block_grid(&blank_tiles(4, 3));
+        assert_eq!((blocks.cols, blocks.rows), (9, 7));
+        // Fully walled, only the tiles themselves are floor
+        for (i, &wall) in blocks.vec.iter().enumerate() {
+            let (x, y) = blocks.coords(i);
+            assert_eq!(wall, x % 2 == 0 || y % 2 == 0, "({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn open_passages_show_as_gaps() {
+        let mut tiles = blank_tiles(2, 2);
+        remove_walls_between_positions(&mut tiles, (0, 0), (1, 0));
+        remove_walls_between_positions(&mut tiles, (1, 0), (1, 1));
+        let blocks = block_grid(&tiles);
+        assert!(!*blocks.index(2, 1));
+        assert!(!*blocks.index(3, 2));
+        // Still walled between (0, 0) and (0, 1), and so is their corner
+        assert!(*blocks.index(1, 2));
+        assert!(*blocks.index(2, 2));
+        // The outer border stays closed
+        assert!((0..5).all(|x| *blocks.index(x, 0) && *blocks.index(x, 4)));
+    }
+}
@@ -0,0 +1,116 @@
+use crate::maze::maze_bounds;
+use crate::tile::Tile;
+use crate::utils::Vec2d;
+
+use macroquad::prelude::*;
+
+// Share of the screen left empty around a fitted maze
+const FIT_MARGIN: f32 = 0.05;
+
+// Keeps the player in view when the maze is bigger than the screen
+#[derive(Debug, Clone)]
+pub struct MazeCamera {
+    pub target: Vec2,
+    pub zoom: f32,
+    // Showing the whole maze, following is paused until released
+    pub fitted: bool,
+}
+
+impl MazeCamera {
+    pub fn new(target: Vec2) -> Self {
+        Self {
+            target,
+            zoom: 1.0,
+            fitted: false,
+        }
+    }
+
+    // Center on `target` but never show past the maze edges, an axis that
+    // fits on screen keeps the plain screen view (the layout centers it)
+    pub fn follow(&mut self, target: Vec2, bounds: Rect) {
+        if self.fitted {
+            return;
+        }
+        let half_w = screen_width() / 2.0;
+        let half_h = screen_height() / 2.0;
+        self.target.x = clamp_axis(target.x, bounds.x, bounds.w, half_w);
+        self.target.y = clamp_axis(target.y, bounds.y, bounds.h, half_h);
+    }
+
+    pub fn fit(&mut self, (center, zoom): (Vec2, f32)) {
+        self.target = center;
+        self.zoom = zoom;
+        self.fitted = true;
+    }
+
+    // Go back to following at normal zoom
+    pub fn release_fit(&mut self) {
+        if self.fitted {
+            self.fitted = false;
+            self.zoom = 1.0;
+        }
+    }
+
+    pub fn to_camera2d(&self) -> Camera2D {
+        let w = screen_width() / self.zoom;
+        let h = screen_height() / self.zoom;
+        Camera2D::from_display_rect(Rect::new(
+            self.target.x - w / 2.0,
+            self.target.y - h / 2.0,
+            w,
+            h,
+        ))
+    }
+}
+
+fn clamp_axis(target: f32, start: f32, len: f32, half_view: f32) -> f32 {
+    if len <= half_view * 2.0 {
+        half_view
+    } else {
+        target.clamp(start + half_view, start + len - half_view)
+    }
+}
+
+// Center and zoom showing all of `bounds` inside a view_w x view_h viewport
+pub fn fit_view(bounds: Rect, view_w: f32, view_h: f32) -> (Vec2, f32) {
+    let usable = 1.0 - FIT_MARGIN * 2.0;
+    let zoom = (view_w * usable / bounds.w).min(view_h * usable / bounds.h);
+    (bounds.center(), zoom)
+}
+
+pub fn fit_camera(tiles: &Vec2d<Tile>) -> (Vec2, f32) {
+    fit_view(maze_bounds(tiles), screen_width(), screen_height())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fitted_view_holds_the_whole_maze() {
+        for bounds in [
+            Rect::new(0.0, 0.0, 2000.0, 500.0),
+            Rect::new(-40.0, 30.0, 300.0, 900.0),
+            Rect::new(10.0, 10.0, 100.0, 75.0),
+        ] {
+            let (view_w, view_h) = (800.0, 600.0);
+            let (center, zoom) = fit_view(bounds, view_w, view_h);
+            assert_eq!(center, bounds.center());
+            // One zoom for both axes keeps the aspect ratio
+            let (seen_w, seen_h) = (view_w / zoom, view_h / zoom);
+            assert!(seen_w >= bounds.w && seen_h >= bounds.h, "{:?}", bounds);
+            // The tighter axis fills the screen up to the margins
+            let filled = (bounds.w / seen_w).max(bounds.h / seen_h);
+            assert!((filled - (1.0 - FIT_MARGIN * 2.0)).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn following_stops_at_the_maze_edges() {
+        assert_eq!(clamp_axis(10.0, 0.0, 1000.0, 200.0), 200.0);
+        assert_eq!(clamp_axis(500.0, 0.0, 1000.0, 200.0), 500.0);
+        assert_eq!(clamp_axis(990.0, 0.0, 1000.0, 200.0), 800.0);
+        // A maze narrower than the screen keeps the screen view
+        assert_eq!(clamp_axis(990.0, 0.0, 300.0, 200.0), 200.0);
+    }
+}
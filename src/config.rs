@@ -0,0 +1,147 @@
+// Startup settings: built-in defaults, overridden by the config file,
+// overridden by the command line. The file holds one setting per line:
+//
+//   # comments and blank lines are ignored
+//   loops = 5        (any flag that takes a value, named without the dashes)
+//   wrap             (a switch, `wrap = true` / `wrap = false` work too)
+//
+// and the command line uses the same names as flags: `--loops 5 --wrap`.
+// Anything set in neither keeps the game's built-in default.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+// Read from the working directory unless `--config PATH` names another file
+pub const CONFIG_FILE: &str = "maze.conf";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    BadLine { line: usize },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::BadLine { line } => {
+                write!(f, "line {}: expected '<name> = <value>' or '<name>'", line)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    // Setting name (no dashes) to its raw value, switches hold "true"
+    values: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (name, value) = match line.split_once('=') {
+                Some((name, value)) => (name.trim(), value.trim()),
+                None => (line, "true"),
+            };
+            if name.is_empty() || value.is_empty() || name.contains(char::is_whitespace) {
+                return Err(ConfigError::BadLine { line: i + 1 });
+            }
+            config.values.insert(name.to_string(), value.to_string());
+        }
+        Ok(config)
+    }
+
+    // Settings from the file at `path`. No file means all defaults, a file
+    // that can't be read or parsed is reported and ignored
+    pub fn load(path: &Path) -> Self {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(err) => {
+                eprintln!("Can't read {}: {}", path.display(), err);
+                return Self::default();
+            }
+        };
+        Self::parse(&text).unwrap_or_else(|err| {
+            eprintln!("Ignoring {}: {}", path.display(), err);
+            Self::default()
+        })
+    }
+
+    // Command line flags on top, `--name value` or a bare `--name` switch.
+    // `args` shouldn't include the program name
+    pub fn with_args(mut self, args: impl IntoIterator<Item = String>) -> Self {
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                continue;
+            };
+            let value = args
+                .next_if(|next| !next.starts_with("--"))
+                .unwrap_or_else(|| "true".to_string());
+            self.values.insert(name.to_string(), value);
+        }
+        self
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    // None when unset or not a valid T, callers fall back to their default
+    pub fn parsed<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.value(name).and_then(|value| value.parse().ok())
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.value(name).is_some_and(|value| value != "false")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn command_line_beats_file_beats_defaults() {
+        let file = Config::parse("# sample\nloops = 5\nseed=3\nwrap\n\nfps-cap = 30 # trailing\n")
+            .unwrap();
+        let config = file.with_args(args("--loops 7 --wrap false --instant"));
+        assert_eq!(config.parsed::<usize>("loops"), Some(7));
+        assert_eq!(config.parsed::<u64>("seed"), Some(3));
+        assert_eq!(config.parsed::<u32>("fps-cap"), Some(30));
+        assert!(!config.flag("wrap"));
+        assert!(config.flag("instant"));
+        // Unset and unparsable settings leave the caller's default
+        assert_eq!(config.parsed::<usize>("rooms").unwrap_or(2), 2);
+        assert_eq!(config.parsed::<usize>("wrap"), None);
+        assert_eq!(
+            Config::load(Path::new("/nonexistent/maze.conf")),
+            Config::default()
+        );
+    }
+
+    #[test]
+    fn bad_lines_are_reported_by_number() {
+        for (text, line) in [
+            ("= 3\n", 1),
+            ("loops = 5\n\nloops =\n", 3),
+            ("two words = 1\n", 1),
+            ("# ok\nwrap\nsome thing\n", 3),
+        ] {
+            assert_eq!(Config::parse(text), Err(ConfigError::BadLine { line }));
+        }
+    }
+}
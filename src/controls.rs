@@ -2,71 +2,203 @@ use crate::player::{Direction, Player};
 
 use macroquad::prelude::*;
 
+// Keys steering one player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBindings {
+    pub up: KeyCode,
+    pub right: KeyCode,
+    pub down: KeyCode,
+    pub left: KeyCode,
+}
+
+pub const ARROW_KEYS: KeyBindings = KeyBindings {
+    up: KeyCode::Up,
+    right: KeyCode::Right,
+    down: KeyCode::Down,
+    left: KeyCode::Left,
+};
+
+pub const WASD_KEYS: KeyBindings = KeyBindings {
+    up: KeyCode::W,
+    right: KeyCode::D,
+    down: KeyCode::S,
+    left: KeyCode::A,
+};
+
+impl KeyBindings {
+    // Directions whose key went down this frame, dropping any pair of
+    // opposites pressed together
+    pub fn pressed(&self, is_pressed: impl Fn(KeyCode) -> bool) -> Vec<Direction> {
+        let pressed: Vec<Direction> = [
+            (self.up, Direction::Up),
+            (self.right, Direction::Right),
+            (self.down, Direction::Down),
+            (self.left, Direction::Left),
+        ]
+        .into_iter()
+        .filter(|&(key, _)| is_pressed(key))
+        .map(|(_, direction)| direction)
+        .collect();
+        pressed
+            .iter()
+            .copied()
+            .filter(|direction| !pressed.contains(&direction.opposite()))
+            .collect()
+    }
+}
+
+// Key sets per player: alone you can use either, in a race player 1 gets
+// the arrows and player 2 WASD
+pub fn bindings_for(players: usize) -> Vec<Vec<KeyBindings>> {
+    match players {
+        1 => vec![vec![ARROW_KEYS, WASD_KEYS]],
+        _ => [ARROW_KEYS, WASD_KEYS]
+            .into_iter()
+            .take(players)
+            .map(|keys| vec![keys])
+            .collect(),
+    }
+}
+
+// Most recent direction pressed on one player's keys. Only new presses steer,
+// so holding two opposite keys can't flip the player back and forth, and
+// pressing the latched direction again changes nothing until it lets go
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct KeyLatch {
+    pub direction: Option<Direction>,
+}
+
+impl KeyLatch {
+    // Direction to turn to this frame, None without a new press or for the
+    // one already latched. `moving` is where the player is heading, once that
+    // isn't the latched direction (stopped by a wall) the latch lets go
+    pub fn update(
+        &mut self,
+        keys: &[KeyBindings],
+        is_pressed: impl Fn(KeyCode) -> bool,
+        moving: Direction,
+    ) -> Option<Direction> {
+        if self.direction.is_some_and(|direction| direction != moving) {
+            self.direction = None;
+        }
+        let pressed: Vec<Direction> = keys.iter().flat_map(|k| k.pressed(&is_pressed)).collect();
+        let direction = pressed
+            .iter()
+            .copied()
+            .find(|direction| !pressed.contains(&direction.opposite()))?;
+        if self.direction == Some(direction) {
+            return None;
+        }
+        self.direction = Some(direction);
+        Some(direction)
+    }
+}
+
+// What a button did this frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonEvent {
+    // Held down over the button, sent every frame it is
+    Pressed,
+    // Let go, over the button or after sliding off it
+    Released,
+    None,
+}
+
+// Rect with hover and press colors. Sliding onto the button while holding
+// the mouse presses it, sliding off keeps it pressed until the mouse is up
 #[derive(Debug)]
-pub struct DirectionButton {
-    rect: Rect,
-    direction: Direction,
+pub struct Button {
+    pub rect: Rect,
     color: Color,
     hover_color: Color,
     pressed_color: Color,
-    triangle_color: Color,
     is_pressed: bool,
 }
 
-impl DirectionButton {
-    pub fn new(x: f32, y: f32, width: f32, height: f32, direction: Direction) -> Self {
+impl Button {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
         Self {
             rect: Rect::new(x, y, width, height),
-            direction,
             color: Color::new(0.5, 0.5, 0.5, 0.3), // Semi-transparent gray
             hover_color: Color::new(0.6, 0.6, 0.6, 0.4),
             pressed_color: Color::new(0.4, 0.4, 0.4, 0.5),
-            triangle_color: Color::new(0.0, 0.0, 0.0, 0.2),
             is_pressed: false,
         }
     }
 
-    pub fn update(&mut self) -> Option<Direction> {
-        let mouse_pos = mouse_position();
-        let mouse_point = Vec2::new(mouse_pos.0, mouse_pos.1);
-        let was_pressed = self.is_pressed;
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.rect.contains(point)
+    }
+
+    // Taps arrive as mouse events too
+    pub fn update(&mut self) -> ButtonEvent {
+        let over = self.contains(mouse_position().into());
+        self.update_with(over, is_mouse_button_down(MouseButton::Left))
+    }
 
-        if self.rect.contains(mouse_point) {
-            if is_mouse_button_down(MouseButton::Left) {
-                self.is_pressed = true;
-                return Some(self.direction);
-            } else {
-                self.is_pressed = false;
-                if was_pressed {
-                    return Some(Direction::None); // Button released
-                }
+    // update() for a pointer that is `over` the button or not, held `down` or not
+    pub fn update_with(&mut self, over: bool, down: bool) -> ButtonEvent {
+        let was_pressed = self.is_pressed;
+        if over {
+            self.is_pressed = down;
+            if down {
+                return ButtonEvent::Pressed;
+            }
+            if was_pressed {
+                return ButtonEvent::Released;
             }
-        } else if self.is_pressed && !is_mouse_button_down(MouseButton::Left) {
+        } else if self.is_pressed && !down {
             self.is_pressed = false;
-            return Some(Direction::None); // Button released (mouse moved off while pressed)
+            return ButtonEvent::Released;
         }
-
-        None
+        ButtonEvent::None
     }
 
     pub fn draw(&self) {
         let color = if self.is_pressed {
             self.pressed_color
-        } else if self
-            .rect
-            .contains(Vec2::new(mouse_position().0, mouse_position().1))
-        {
+        } else if self.contains(mouse_position().into()) {
             self.hover_color
         } else {
             self.color
         };
-
         draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, color);
+    }
+}
+
+#[derive(Debug)]
+pub struct DirectionButton {
+    button: Button,
+    direction: Direction,
+    triangle_color: Color,
+}
+
+impl DirectionButton {
+    pub fn new(x: f32, y: f32, width: f32, height: f32, direction: Direction) -> Self {
+        Self {
+            button: Button::new(x, y, width, height),
+            direction,
+            triangle_color: Color::new(0.0, 0.0, 0.0, 0.2),
+        }
+    }
+
+    // The direction while held, Direction::None once released
+    pub fn update(&mut self) -> Option<Direction> {
+        match self.button.update() {
+            ButtonEvent::Pressed => Some(self.direction),
+            ButtonEvent::Released => Some(Direction::None),
+            ButtonEvent::None => None,
+        }
+    }
+
+    pub fn draw(&self) {
+        self.button.draw();
 
         // Draw direction arrow
-        let center_x = self.rect.x + self.rect.w / 2.0;
-        let center_y = self.rect.y + self.rect.h / 2.0;
-        let arrow_size = self.rect.w.min(self.rect.h) * 0.5;
+        let rect = self.button.rect;
+        let center_x = rect.x + rect.w / 2.0;
+        let center_y = rect.y + rect.h / 2.0;
+        let arrow_size = rect.w.min(rect.h) * 0.5;
 
         match self.direction {
             Direction::Up => {
@@ -106,9 +238,48 @@ impl DirectionButton {
     }
 }
 
+// Labelled button that fires once per tap or click, when released over it
+#[derive(Debug)]
+pub struct LabelButton {
+    button: Button,
+    label: String,
+}
+
+impl LabelButton {
+    pub fn new(x: f32, y: f32, width: f32, height: f32, label: &str) -> Self {
+        Self {
+            button: Button::new(x, y, width, height),
+            label: label.to_string(),
+        }
+    }
+
+    // True on the frame it is let go with the pointer still over it
+    pub fn update(&mut self) -> bool {
+        self.button.update() == ButtonEvent::Released
+            && self.button.contains(mouse_position().into())
+    }
+
+    pub fn draw(&self) {
+        self.button.draw();
+
+        let rect = self.button.rect;
+        let font_size = rect.h * 0.6;
+        let size = measure_text(&self.label, None, font_size as u16, 1.0);
+        draw_text(
+            &self.label,
+            rect.center().x - size.width / 2.0,
+            rect.center().y + size.offset_y / 2.0,
+            font_size,
+            WHITE,
+        );
+    }
+}
+
 // Control pad with all four direction buttons
 pub struct ControlPad {
     buttons: [DirectionButton; 4],
+    // One per player, in the order of the bindings
+    latches: Vec<KeyLatch>,
 }
 
 impl ControlPad {
@@ -144,29 +315,26 @@ impl ControlPad {
 
         Self {
             buttons: [up, right, down, left],
+            latches: Vec::new(),
         }
     }
 
-    pub fn update(&mut self, player: &mut Player) {
+    // On screen buttons steer the first player, keys go to whoever they're bound to
+    pub fn update(&mut self, players: &mut [Player], bindings: &[Vec<KeyBindings>]) {
         for button in &mut self.buttons {
-            match button.update() {
-                Some(direction) => {
-                    if direction != Direction::None {
-                        player.set_direction(direction);
-                    }
-                }
-                None => {}
+            if let Some(direction) = button.update()
+                && direction != Direction::None
+                && let Some(player) = players.first_mut()
+            {
+                player.set_direction(direction);
             }
         }
 
-        if is_key_down(KeyCode::Up) || is_key_down(KeyCode::W) {
-            player.set_direction(Direction::Up);
-        } else if is_key_down(KeyCode::Right) || is_key_down(KeyCode::D) {
-            player.set_direction(Direction::Right);
-        } else if is_key_down(KeyCode::Down) || is_key_down(KeyCode::S) {
-            player.set_direction(Direction::Down);
-        } else if is_key_down(KeyCode::Left) || is_key_down(KeyCode::A) {
-            player.set_direction(Direction::Left);
+        self.latches.resize(bindings.len(), KeyLatch::default());
+        for ((player, keys), latch) in players.iter_mut().zip(bindings).zip(&mut self.latches) {
+            if let Some(direction) = latch.update(keys, is_key_pressed, player.current_direction) {
+                player.set_direction(direction);
+            }
         }
     }
 
@@ -176,3 +344,120 @@ impl ControlPad {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Run one frame of `latch` with only `keys` pressed this frame
+    fn frame(latch: &mut KeyLatch, keys: &[KeyCode], moving: Direction) -> Option<Direction> {
+        latch.update(&[ARROW_KEYS], |key| keys.contains(&key), moving)
+    }
+
+    #[test]
+    fn opposite_keys_cancel_out() {
+        assert!(
+            ARROW_KEYS
+                .pressed(|key| key == KeyCode::Left || key == KeyCode::Right)
+                .is_empty()
+        );
+        let mut latch = KeyLatch::default();
+        assert_eq!(
+            frame(
+                &mut latch,
+                &[KeyCode::Left, KeyCode::Right],
+                Direction::None
+            ),
+            None
+        );
+        // The third key still steers
+        assert_eq!(
+            frame(
+                &mut latch,
+                &[KeyCode::Left, KeyCode::Right, KeyCode::Up],
+                Direction::None
+            ),
+            Some(Direction::Up)
+        );
+    }
+
+    #[test]
+    fn last_press_wins() {
+        let mut latch = KeyLatch::default();
+        assert_eq!(
+            frame(&mut latch, &[KeyCode::Right], Direction::None),
+            Some(Direction::Right)
+        );
+        // Held keys don't show up as presses, nothing changes
+        assert_eq!(frame(&mut latch, &[], Direction::Right), None);
+        assert_eq!(
+            frame(&mut latch, &[KeyCode::Down], Direction::Right),
+            Some(Direction::Down)
+        );
+        assert_eq!(latch.direction, Some(Direction::Down));
+    }
+
+    #[test]
+    fn latched_direction_is_ignored_until_a_wall_stop() {
+        let mut latch = KeyLatch::default();
+        assert_eq!(
+            frame(&mut latch, &[KeyCode::Up], Direction::None),
+            Some(Direction::Up)
+        );
+        assert_eq!(frame(&mut latch, &[KeyCode::Up], Direction::Up), None);
+        // Stopped by a wall, the same key steers again
+        assert_eq!(
+            frame(&mut latch, &[KeyCode::Up], Direction::None),
+            Some(Direction::Up)
+        );
+    }
+
+    #[test]
+    fn button_hit_test_covers_its_rect() {
+        let button = Button::new(10.0, 20.0, 30.0, 40.0);
+        assert!(button.contains(vec2(25.0, 40.0)));
+        // Top left edge is in, bottom right edge is out
+        assert!(button.contains(vec2(10.0, 20.0)));
+        assert!(!button.contains(vec2(40.0, 60.0)));
+        assert!(!button.contains(vec2(9.9, 40.0)));
+        assert!(!button.contains(vec2(25.0, 60.1)));
+    }
+
+    #[test]
+    fn button_presses_while_held_and_releases_once() {
+        let mut button = Button::new(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(button.update_with(true, false), ButtonEvent::None);
+        assert_eq!(button.update_with(true, true), ButtonEvent::Pressed);
+        assert_eq!(button.update_with(true, true), ButtonEvent::Pressed);
+        assert_eq!(button.update_with(true, false), ButtonEvent::Released);
+        assert_eq!(button.update_with(true, false), ButtonEvent::None);
+    }
+
+    #[test]
+    fn button_slid_off_stays_pressed_until_let_go() {
+        let mut button = Button::new(0.0, 0.0, 10.0, 10.0);
+        button.update_with(true, true);
+        assert_eq!(button.update_with(false, true), ButtonEvent::None);
+        assert_eq!(button.update_with(false, false), ButtonEvent::Released);
+        assert_eq!(button.update_with(false, false), ButtonEvent::None);
+        // Sliding on while held presses it
+        assert_eq!(button.update_with(false, true), ButtonEvent::None);
+        assert_eq!(button.update_with(true, true), ButtonEvent::Pressed);
+    }
+
+    #[test]
+    fn each_player_in_a_race_gets_its_own_keys() {
+        assert_eq!(bindings_for(1), vec![vec![ARROW_KEYS, WASD_KEYS]]);
+        let bindings = bindings_for(2);
+        assert_eq!(bindings, vec![vec![ARROW_KEYS], vec![WASD_KEYS]]);
+        // Up arrow and A together steer one player each
+        let keys = [KeyCode::Up, KeyCode::A];
+        let steered: Vec<Option<Direction>> = bindings
+            .iter()
+            .map(|player_keys| {
+                KeyLatch::default().update(player_keys, |key| keys.contains(&key), Direction::None)
+            })
+            .collect();
+        assert_eq!(steered, vec![Some(Direction::Up), Some(Direction::Left)]);
+    }
+}
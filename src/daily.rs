@@ -0,0 +1,59 @@
+// Grid everyone plays on `--daily`, whatever their screen size
+pub const DAILY_COLS: usize = 40;
+pub const DAILY_ROWS: usize = 24;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+// (year, month, day) in UTC of a unix timestamp
+pub fn utc_date(unix_secs: f64) -> (i64, u32, u32) {
+    // Days to civil date, shifted so years start in March and leap days end them
+    let days = (unix_secs / SECONDS_PER_DAY).floor() as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+// The date read as the number YYYYMMDD
+pub fn daily_seed((year, month, day): (i64, u32, u32)) -> u64 {
+    year as u64 * 10_000 + month as u64 * 100 + day as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utc_dates_of_known_timestamps() {
+        assert_eq!(utc_date(0.0), (1970, 1, 1));
+        assert_eq!(utc_date(946_598_400.0 + 86_399.9), (1999, 12, 31));
+        // Leap days, 2100 has none
+        assert_eq!(utc_date(951_782_400.0), (2000, 2, 29));
+        assert_eq!(utc_date(1_709_164_800.0), (2024, 2, 29));
+        assert_eq!(utc_date(1_709_251_200.0), (2024, 3, 1));
+        assert_eq!(utc_date(4_107_542_400.0 - 1.0), (2100, 2, 28));
+    }
+
+    #[test]
+    fn daily_seed_is_stable_and_changes_every_day() {
+        assert_eq!(daily_seed((2024, 2, 29)), 20_240_229);
+        assert_eq!(
+            daily_seed(utc_date(1_709_164_800.0)),
+            daily_seed(utc_date(1_709_164_800.0 + 3_600.0))
+        );
+        let days: Vec<u64> = (0..400)
+            .map(|day| daily_seed(utc_date(1_709_164_800.0 + day as f64 * SECONDS_PER_DAY)))
+            .collect();
+        assert!(days.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
@@ -0,0 +1,546 @@
+// Plain text maze format:
+//
+//   # comments and blank lines are ignored
+//   <cols> <rows> [wrap]     (wrap: edges connect to the opposite side)
+//   <rows> lines of <cols> hex wall masks (Left=1, Top=2, Right=4, Bottom=8)
+//   exit <col> <row>        (optional)
+//   entrance <col> <row>    (optional, either order)
+//
+// Tokens are whitespace separated, so hand-edited files can be aligned freely.
+//
+// A pack holds several mazes in one file, each in the format above, with a
+// `---` line between them. Line numbers in errors count from the start of
+// the maze they are in.
+
+use crate::maze::{mark_entrance, mark_exit, restore_walls, wall_inconsistencies};
+use crate::tile::{PATH_COLOR, Tile, WALL_COLOR};
+use crate::utils::{Vec2d, Vec2dError};
+
+use std::fmt;
+use std::path::Path;
+
+// Line between two mazes of a pack
+pub const PACK_DELIMITER: &str = "---";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    MissingHeader,
+    BadHeader {
+        line: usize,
+    },
+    BadMask {
+        line: usize,
+        token: String,
+    },
+    WrongColumnCount {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    WrongRowCount {
+        expected: usize,
+        found: usize,
+    },
+    BadExit {
+        line: usize,
+    },
+    BadEntrance {
+        line: usize,
+    },
+    UnexpectedLine {
+        line: usize,
+    },
+    // Neighbors disagreeing on their shared wall
+    InconsistentWalls {
+        a: (usize, usize),
+        b: (usize, usize),
+    },
+    Grid(Vec2dError),
+    // Maze number `index` (from 0) of a pack failed to parse
+    InPack {
+        index: usize,
+        error: Box<ParseError>,
+    },
+    Unreadable(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingHeader => write!(f, "missing '<cols> <rows>' header"),
+            ParseError::BadHeader { line } => {
+                write!(f, "line {}: expected '<cols> <rows>' header", line)
+            }
+            ParseError::BadMask { line, token } => {
+                write!(f, "line {}: '{}' is not a wall mask (0-f)", line, token)
+            }
+            ParseError::WrongColumnCount {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: expected {} tiles, found {}",
+                line, expected, found
+            ),
+            ParseError::WrongRowCount { expected, found } => {
+                write!(f, "expected {} rows, found {}", expected, found)
+            }
+            ParseError::BadExit { line } => {
+                write!(
+                    f,
+                    "line {}: expected 'exit <col> <row>' inside the maze",
+                    line
+                )
+            }
+            ParseError::BadEntrance { line } => {
+                write!(
+                    f,
+                    "line {}: expected 'entrance <col> <row>' inside the maze",
+                    line
+                )
+            }
+            ParseError::UnexpectedLine { line } => write!(f, "line {}: unexpected content", line),
+            ParseError::InconsistentWalls { a, b } => {
+                write!(f, "tiles {:?} and {:?} disagree on their shared wall", a, b)
+            }
+            ParseError::Grid(err) => write!(f, "{}", err),
+            ParseError::InPack { index, error } => write!(f, "maze {}: {}", index + 1, error),
+            ParseError::Unreadable(err) => write!(f, "can't read: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub fn maze_to_string(tiles: &Vec2d<Tile>) -> String {
+    let mut out = format!("{} {}", tiles.cols, tiles.rows);
+    out.push_str(if tiles.wrap { " wrap\n" } else { "\n" });
+    for row in tiles.rows_iter() {
+        let masks: Vec<String> = row
+            .iter()
+            .map(|tile| format!("{:x}", tile.wall_mask()))
+            .collect();
+        out.push_str(&masks.join(" "));
+        out.push('\n');
+    }
+    if let Some(exit) = tiles.vec.iter().find(|tile| tile.exit) {
+        out.push_str(&format!("exit {} {}\n", exit.col, exit.row));
+    }
+    if let Some(entrance) = tiles.vec.iter().find(|tile| tile.entrance) {
+        out.push_str(&format!("entrance {} {}\n", entrance.col, entrance.row));
+    }
+    out
+}
+
+// The maze as one line of JSON for external tools:
+// {"cols": C, "rows": R, "wrap": bool, "walls": [[mask, ...], ...],
+//  "exit": [col, row] | null, "entrance": [col, row] | null, "player": [col, row]}
+// with `walls` row by row and the same masks as the text format
+pub fn to_json(tiles: &Vec2d<Tile>, player: (usize, usize)) -> String {
+    let point = |pos: Option<(usize, usize)>| match pos {
+        Some((col, row)) => format!("[{}, {}]", col, row),
+        None => "null".to_string(),
+    };
+    let marked = |is_marked: fn(&Tile) -> bool| {
+        tiles
+            .vec
+            .iter()
+            .find(|tile| is_marked(tile))
+            .map(|tile| (tile.col, tile.row))
+    };
+    let rows: Vec<String> = tiles
+        .rows_iter()
+        .map(|row| {
+            let masks: Vec<String> = row
+                .iter()
+                .map(|tile| tile.wall_mask().to_string())
+                .collect();
+            format!("[{}]", masks.join(", "))
+        })
+        .collect();
+    format!(
+        "{{\"cols\": {}, \"rows\": {}, \"wrap\": {}, \"walls\": [{}], \"exit\": {}, \"entrance\": {}, \"player\": {}}}",
+        tiles.cols,
+        tiles.rows,
+        tiles.wrap,
+        rows.join(", "),
+        point(marked(|tile| tile.exit)),
+        point(marked(|tile| tile.entrance)),
+        point(Some(player)),
+    )
+}
+
+// Dense one-hex-digit-per-tile wall dump for logs and assertion messages
+pub fn debug_grid(tiles: &Vec2d<Tile>) -> String {
+    let mut out = String::with_capacity((tiles.cols + 1) * tiles.rows);
+    for row in tiles.rows_iter() {
+        for tile in row {
+            out.push_str(&format!("{:x}", tile.wall_mask()));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub fn pack_to_string(mazes: &[Vec2d<Tile>]) -> String {
+    let blocks: Vec<String> = mazes.iter().map(maze_to_string).collect();
+    blocks.join(&format!("{}\n", PACK_DELIMITER))
+}
+
+// Every maze of a pack in order. Each one is checked on its own and the first
+// broken one fails the whole pack, blocks holding only comments are skipped
+pub fn pack_from_string(s: &str) -> Result<Vec<Vec2d<Tile>>, ParseError> {
+    let mut blocks = vec![String::new()];
+    for line in s.lines() {
+        if line.trim() == PACK_DELIMITER {
+            blocks.push(String::new());
+        } else {
+            let block = blocks.last_mut().unwrap();
+            block.push_str(line);
+            block.push('\n');
+        }
+    }
+    blocks
+        .iter()
+        .filter(|block| {
+            block
+                .lines()
+                .map(str::trim)
+                .any(|line| !line.is_empty() && !line.starts_with('#'))
+        })
+        .enumerate()
+        .map(|(index, block)| {
+            maze_from_string(block).map_err(|error| ParseError::InPack {
+                index,
+                error: Box::new(error),
+            })
+        })
+        .collect()
+}
+
+pub fn load_pack(path: &Path) -> Result<Vec<Vec2d<Tile>>, ParseError> {
+    let text =
+        std::fs::read_to_string(path).map_err(|err| ParseError::Unreadable(err.to_string()))?;
+    pack_from_string(&text)
+}
+
+// Every parseable `.txt` maze in `dir` sorted by file name, broken files are
+// reported and skipped
+pub fn load_level_dir(dir: &Path) -> std::io::Result<Vec<(String, Vec2d<Tile>)>> {
+    let mut paths: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    paths.sort();
+
+    let mut levels = Vec::new();
+    for path in paths {
+        let name = path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        match std::fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| maze_from_string(&text).map_err(|err| err.to_string()))
+        {
+            Ok(tiles) => levels.push((name, tiles)),
+            Err(err) => eprintln!("Skipping {}: {}", path.display(), err),
+        }
+    }
+    Ok(levels)
+}
+
+// Tiles are laid out one pixel apart, callers place them on screen afterwards
+pub fn maze_from_string(s: &str) -> Result<Vec2d<Tile>, ParseError> {
+    let mut lines = s
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+    let (header_line, header) = lines.next().ok_or(ParseError::MissingHeader)?;
+    let header_tokens: Vec<&str> = header.split_whitespace().collect();
+    let (dims, wrap) = match header_tokens[..] {
+        [cols, rows] => ([cols, rows], false),
+        [cols, rows, "wrap"] => ([cols, rows], true),
+        _ => return Err(ParseError::BadHeader { line: header_line }),
+    };
+    let [Ok(cols), Ok(rows)] = dims.map(|token| token.parse::<usize>()) else {
+        return Err(ParseError::BadHeader { line: header_line });
+    };
+    if cols == 0 || rows == 0 {
+        return Err(ParseError::BadHeader { line: header_line });
+    }
+
+    let mut tiles = Vec::with_capacity(cols * rows);
+    let mut masks = Vec::with_capacity(cols * rows);
+    for row in 0..rows {
+        let (line, content) = lines.next().ok_or(ParseError::WrongRowCount {
+            expected: rows,
+            found: row,
+        })?;
+        let tokens: Vec<&str> = content.split_whitespace().collect();
+        if tokens.len() != cols {
+            return Err(ParseError::WrongColumnCount {
+                line,
+                expected: cols,
+                found: tokens.len(),
+            });
+        }
+        for (col, token) in tokens.into_iter().enumerate() {
+            let mask = u8::from_str_radix(token, 16)
+                .ok()
+                .filter(|mask| *mask <= 0xf)
+                .ok_or_else(|| ParseError::BadMask {
+                    line,
+                    token: token.to_string(),
+                })?;
+            let mut tile = Tile::new(col, row, col as f32, row as f32, 1.0, 1.0, WALL_COLOR);
+            // Any opening makes it floor, like carving it would
+            if mask != 0xf {
+                tile.color = PATH_COLOR;
+            }
+            tiles.push(tile);
+            masks.push(mask);
+        }
+    }
+
+    let mut tiles = Vec2d::try_new(tiles, rows, cols).map_err(ParseError::Grid)?;
+    tiles.wrap = wrap;
+    restore_walls(&mut tiles, &masks);
+    if let Some(&(a, b)) = wall_inconsistencies(&tiles).first() {
+        return Err(ParseError::InconsistentWalls { a, b });
+    }
+
+    // Exit and entrance lines, each at most once
+    let (mut has_exit, mut has_entrance) = (false, false);
+    for (line, content) in lines {
+        let tokens: Vec<&str> = content.split_whitespace().collect();
+        let (is_exit, pos) = match tokens[..] {
+            ["exit", col, row] if !has_exit => (true, (col, row)),
+            ["entrance", col, row] if !has_entrance => (false, (col, row)),
+            _ => return Err(ParseError::UnexpectedLine { line }),
+        };
+        let pos = pos
+            .0
+            .parse::<usize>()
+            .ok()
+            .zip(pos.1.parse::<usize>().ok())
+            .filter(|&(col, row)| col < cols && row < rows);
+        match (is_exit, pos) {
+            (true, Some(pos)) => mark_exit(&mut tiles, pos),
+            (false, Some(pos)) => mark_entrance(&mut tiles, pos),
+            (true, None) => return Err(ParseError::BadExit { line }),
+            (false, None) => return Err(ParseError::BadEntrance { line }),
+        }
+        has_exit |= is_exit;
+        has_entrance |= !is_exit;
+    }
+
+    Ok(tiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{blank_tiles, mark_exit, remove_walls_between_positions};
+    use crate::tile::Wall;
+
+    #[test]
+    fn comments_blank_lines_and_spacing_are_ignored() {
+        let text = "# hand edited\n\n   2   1  \n\n  b\t  e   \n\n# done\n";
+        let tiles = maze_from_string(text).unwrap();
+        assert_eq!((tiles.cols, tiles.rows), (2, 1));
+        assert!(!tiles.index(0, 0).walls().contains(&Wall::Right));
+        assert!(!tiles.index(1, 0).walls().contains(&Wall::Left));
+        assert_eq!(tiles.index(0, 0).wall_mask(), 0xb);
+    }
+
+    #[test]
+    fn each_failure_has_its_own_error() {
+        assert_eq!(
+            maze_from_string("# nothing\n").unwrap_err(),
+            ParseError::MissingHeader
+        );
+        assert_eq!(
+            maze_from_string("2 x\n").unwrap_err(),
+            ParseError::BadHeader { line: 1 }
+        );
+        assert_eq!(
+            maze_from_string("2 1\nf\n").unwrap_err(),
+            ParseError::WrongColumnCount {
+                line: 2,
+                expected: 2,
+                found: 1
+            }
+        );
+        assert_eq!(
+            maze_from_string("1 2\nf\n").unwrap_err(),
+            ParseError::WrongRowCount {
+                expected: 2,
+                found: 1
+            }
+        );
+        assert_eq!(
+            maze_from_string("1 1\ng\n").unwrap_err(),
+            ParseError::BadMask {
+                line: 2,
+                token: "g".to_string()
+            }
+        );
+        assert_eq!(
+            maze_from_string("1 1\nf\nexit 1 0\n").unwrap_err(),
+            ParseError::BadExit { line: 3 }
+        );
+    }
+
+    #[test]
+    fn pack_round_trips_two_mazes() {
+        let mut corridor = blank_tiles(2, 1);
+        remove_walls_between_positions(&mut corridor, (0, 0), (1, 0));
+        let mut column = blank_tiles(1, 2);
+        mark_exit(&mut column, (0, 1));
+        let mazes = vec![corridor, column];
+        let parsed = pack_from_string(&pack_to_string(&mazes)).unwrap();
+        let texts: Vec<String> = parsed.iter().map(maze_to_string).collect();
+        let expected: Vec<String> = mazes.iter().map(maze_to_string).collect();
+        assert_eq!(texts, expected);
+    }
+
+    #[test]
+    fn comment_only_blocks_do_not_count_toward_the_index() {
+        let text = "1 1\nf\n---\n# just a note\n---\n1 1\nf\n---\n1 1\ng\n";
+        assert_eq!(
+            pack_from_string(text).unwrap_err(),
+            ParseError::InPack {
+                index: 2,
+                error: Box::new(ParseError::BadMask {
+                    line: 2,
+                    token: "g".to_string()
+                })
+            }
+        );
+    }
+
+    // Just enough of a JSON reader to check to_json: numbers, bools, null,
+    // arrays and objects with plain string keys
+    #[derive(Debug, PartialEq)]
+    enum Json {
+        Null,
+        Bool(bool),
+        Number(f64),
+        Array(Vec<Json>),
+        Object(Vec<(String, Json)>),
+    }
+
+    fn parse_json(text: &str) -> Json {
+        fn value(s: &mut &str) -> Json {
+            *s = s.trim_start();
+            let json = if let Some(rest) = s.strip_prefix('[') {
+                *s = rest;
+                let mut items = Vec::new();
+                while !s.trim_start().starts_with(']') {
+                    items.push(value(s));
+                    *s = s.trim_start().strip_prefix(',').unwrap_or(s);
+                }
+                Json::Array(items)
+            } else if let Some(rest) = s.strip_prefix('{') {
+                *s = rest;
+                let mut fields = Vec::new();
+                while !s.trim_start().starts_with('}') {
+                    let key = s.trim_start().strip_prefix('"').expect("key");
+                    let end = key.find('"').unwrap();
+                    let name = key[..end].to_string();
+                    *s = key[end + 1..]
+                        .trim_start()
+                        .strip_prefix(':')
+                        .expect("colon");
+                    fields.push((name, value(s)));
+                    *s = s.trim_start().strip_prefix(',').unwrap_or(s);
+                }
+                Json::Object(fields)
+            } else {
+                let end = s.find([',', ']', '}']).unwrap_or(s.len());
+                let token = s[..end].trim();
+                let json = match token {
+                    "null" => Json::Null,
+                    "true" => Json::Bool(true),
+                    "false" => Json::Bool(false),
+                    number => Json::Number(number.parse().expect("number")),
+                };
+                *s = &s[end..];
+                return json;
+            };
+            // Drop the closing bracket or brace
+            *s = &s.trim_start()[1..];
+            json
+        }
+        let mut s = text;
+        let json = value(&mut s);
+        assert!(s.trim().is_empty(), "trailing {:?}", s);
+        json
+    }
+
+    fn field<'a>(json: &'a Json, name: &str) -> &'a Json {
+        let Json::Object(fields) = json else {
+            panic!("not an object");
+        };
+        &fields.iter().find(|(key, _)| key == name).unwrap().1
+    }
+
+    fn numbers(json: &Json) -> Vec<usize> {
+        let Json::Array(items) = json else {
+            panic!("not an array");
+        };
+        items
+            .iter()
+            .map(|item| match item {
+                Json::Number(n) => *n as usize,
+                _ => panic!("not a number"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn json_parses_back_to_the_same_maze() {
+        let mut tiles = blank_tiles(3, 2);
+        remove_walls_between_positions(&mut tiles, (0, 0), (1, 0));
+        remove_walls_between_positions(&mut tiles, (1, 0), (1, 1));
+        remove_walls_between_positions(&mut tiles, (1, 1), (2, 1));
+        mark_exit(&mut tiles, (2, 1));
+        let json = parse_json(&to_json(&tiles, (1, 0)));
+        assert_eq!(field(&json, "cols"), &Json::Number(3.0));
+        assert_eq!(field(&json, "rows"), &Json::Number(2.0));
+        assert_eq!(field(&json, "wrap"), &Json::Bool(false));
+        assert_eq!(numbers(field(&json, "exit")), vec![2, 1]);
+        assert_eq!(field(&json, "entrance"), &Json::Null);
+        assert_eq!(numbers(field(&json, "player")), vec![1, 0]);
+
+        let Json::Array(rows) = field(&json, "walls") else {
+            panic!("walls are not an array");
+        };
+        assert_eq!(rows.len(), 2);
+        let masks: Vec<u8> = rows
+            .iter()
+            .flat_map(numbers)
+            .map(|mask| mask as u8)
+            .collect();
+        let mut parsed = blank_tiles(3, 2);
+        restore_walls(&mut parsed, &masks);
+        for (tile, back) in tiles.vec.iter().zip(&parsed.vec) {
+            assert_eq!(tile.walls(), back.walls());
+        }
+    }
+
+    #[test]
+    fn debug_grid_prints_one_hex_mask_per_tile() {
+        // Open along the top and down the right: a backwards L
+        let mut tiles = blank_tiles(2, 2);
+        remove_walls_between_positions(&mut tiles, (0, 0), (1, 0));
+        remove_walls_between_positions(&mut tiles, (1, 0), (1, 1));
+        assert_eq!(debug_grid(&tiles), "b6\nfd\n");
+        assert_eq!(debug_grid(&blank_tiles(3, 1)), "fff\n");
+    }
+}
@@ -0,0 +1,73 @@
+use macroquad::prelude::*;
+
+// Seconds between recorded samples, positions in between are interpolated
+const SAMPLE_INTERVAL: f32 = 0.05;
+
+// Where the player was over a run, (seconds since the start, screen position)
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    pub samples: Vec<(f32, Vec2)>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, time: f32, pos: Vec2) {
+        if self
+            .samples
+            .last()
+            .is_none_or(|&(last, _)| time - last >= SAMPLE_INTERVAL)
+        {
+            self.samples.push((time, pos));
+        }
+    }
+
+    // Position at `time`, between the two nearest samples. The ghost waits on
+    // the first sample before the run and stays on the last one after it
+    pub fn position_at(&self, time: f32) -> Option<Vec2> {
+        let next = self.samples.partition_point(|&(t, _)| t < time);
+        match (
+            next.checked_sub(1).map(|i| self.samples[i]),
+            self.samples.get(next),
+        ) {
+            (Some((t0, a)), Some(&(t1, b))) => Some(a.lerp(b, (time - t0) / (t1 - t0))),
+            (Some((_, a)), None) => Some(a),
+            (None, Some(&(_, b))) => Some(b),
+            (None, None) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ghost_interpolates_between_the_nearest_samples() {
+        let mut run = Recording::new();
+        assert_eq!(run.position_at(1.0), None);
+        run.record(0.0, vec2(0.0, 0.0));
+        run.record(1.0, vec2(10.0, 0.0));
+        run.record(2.0, vec2(10.0, 20.0));
+        assert_eq!(run.position_at(0.5), Some(vec2(5.0, 0.0)));
+        assert_eq!(run.position_at(1.75), Some(vec2(10.0, 15.0)));
+        assert_eq!(run.position_at(1.0), Some(vec2(10.0, 0.0)));
+        // Clamped to the ends of the run
+        assert_eq!(run.position_at(-1.0), Some(vec2(0.0, 0.0)));
+        assert_eq!(run.position_at(5.0), Some(vec2(10.0, 20.0)));
+    }
+
+    #[test]
+    fn samples_closer_than_the_interval_are_dropped() {
+        let mut run = Recording::new();
+        run.record(0.0, Vec2::ZERO);
+        run.record(SAMPLE_INTERVAL / 2.0, Vec2::ONE);
+        run.record(SAMPLE_INTERVAL, Vec2::ONE);
+        assert_eq!(
+            run.samples,
+            vec![(0.0, Vec2::ZERO), (SAMPLE_INTERVAL, Vec2::ONE)]
+        );
+    }
+}
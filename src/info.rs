@@ -0,0 +1,94 @@
+use crate::maze::{Algorithm, count_loops, dead_ends};
+use crate::solver::solve_astar;
+use crate::tile::Tile;
+use crate::utils::Vec2d;
+
+// How the current maze was made and what came out, for the I panel
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MazeInfo {
+    // None for mazes loaded from a file
+    pub algorithm: Option<Algorithm>,
+    pub cols: usize,
+    pub rows: usize,
+    // Seed the RNG started the session from
+    pub seed: u64,
+    // Loops as a share of all internal walls, what remove_random_walls picks
+    pub removed_share: f32,
+    pub dead_ends: usize,
+    pub loops: usize,
+    // Steps from the entrance to the exit, None when it can't be reached
+    pub solution_len: Option<usize>,
+}
+
+impl MazeInfo {
+    pub fn gather(
+        tiles: &Vec2d<Tile>,
+        algorithm: Option<Algorithm>,
+        seed: u64,
+        entrance: (usize, usize),
+        exit: (usize, usize),
+    ) -> Self {
+        let loops = count_loops(tiles);
+        let internal_walls = (tiles.rows - 1) * tiles.cols + (tiles.cols - 1) * tiles.rows;
+        Self {
+            algorithm,
+            cols: tiles.cols,
+            rows: tiles.rows,
+            seed,
+            removed_share: loops as f32 / internal_walls.max(1) as f32,
+            dead_ends: dead_ends(tiles).len(),
+            loops,
+            solution_len: solve_astar(tiles, entrance, exit).map(|path| path.len() - 1),
+        }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let algorithm = match self.algorithm {
+            Some(algorithm) => format!("{:?}", algorithm),
+            None => "loaded".to_string(),
+        };
+        let solution = match self.solution_len {
+            Some(len) => format!("{} steps", len),
+            None => "none".to_string(),
+        };
+        vec![
+            format!("Algorithm: {}", algorithm),
+            format!("Size: {} x {}", self.cols, self.rows),
+            format!("Seed: {}", self.seed),
+            format!("Walls removed: {:.1}%", self.removed_share * 100.0),
+            format!("Dead ends: {}", self.dead_ends),
+            format!("Loops: {}", self.loops),
+            format!("Solution: {}", solution),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{blank_tiles, carve, finish_generation};
+    use crate::settings::Settings;
+
+    #[test]
+    fn info_is_filled_in_after_generation() {
+        macroquad::rand::srand(5);
+        let mut tiles = blank_tiles(12, 8);
+        carve(&mut tiles, Algorithm::default());
+        let (_, entrance, exit) =
+            finish_generation(&mut tiles, false, Some(2), &Settings::default());
+        let info = MazeInfo::gather(&tiles, Some(Algorithm::default()), 5, entrance, exit);
+        assert_ne!(info, MazeInfo::default());
+        assert_eq!((info.cols, info.rows, info.seed), (12, 8, 5));
+        assert!(info.loops >= 2 && info.removed_share > 0.0);
+        assert!(info.dead_ends > 0);
+        assert!(info.solution_len.is_some_and(|len| len > 0));
+        let lines = info.lines();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[1], "Size: 12 x 8");
+        assert!(
+            !lines
+                .iter()
+                .any(|line| line.ends_with("none") || line.contains("loaded"))
+        );
+    }
+}
@@ -0,0 +1,26 @@
+pub mod batch;
+pub mod benchmark;
+pub mod blocks;
+pub mod camera;
+pub mod config;
+pub mod controls;
+pub mod daily;
+pub mod format;
+pub mod ghost;
+pub mod info;
+pub mod maze;
+pub mod minimap;
+pub mod overlay;
+pub mod particles;
+pub mod peek;
+pub mod player;
+pub mod reveal;
+pub mod screenshot;
+pub mod settings;
+pub mod setup;
+pub mod solver;
+pub mod stats;
+pub mod theme;
+pub mod thumbnail;
+pub mod tile;
+pub mod utils;
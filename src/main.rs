@@ -1,28 +1,202 @@
-mod controls;
-mod maze;
-mod player;
-mod tile;
-mod utils;
-
-use controls::ControlPad;
-use maze::*;
-use player::Player;
+use maze::batch::export_batch;
+use maze::benchmark::{compare_algorithms, print_comparison};
+use maze::blocks::draw_block_style;
+use maze::camera::{MazeCamera, fit_camera};
+use maze::config::{CONFIG_FILE, Config};
+use maze::controls::{ControlPad, LabelButton, bindings_for};
+use maze::daily::{DAILY_COLS, DAILY_ROWS, daily_seed, utc_date};
+use maze::format::{load_level_dir, load_pack, maze_from_string, to_json};
+use maze::ghost::Recording;
+use maze::info::MazeInfo;
+use maze::maze::*;
+use maze::minimap::Minimap;
+use maze::overlay;
+use maze::particles::Particles;
+use maze::peek::{Peek, peek_cooldown};
+use maze::player::{
+    Direction, PLAYER_TILES_PER_SECOND, Player, player_on_tile, spawn_players, teleport_pads,
+};
+use maze::reveal::FloodReveal;
+use maze::screenshot::{save_screenshot, screenshot_name};
+use maze::settings::Settings;
+use maze::setup::{Setup, SetupError};
+use maze::solver;
+use maze::stats::{
+    FpsCounter, Stats, beat_the_clock, efficiency, explored_share, frame_sleep, time_limit_for,
+};
+use maze::theme::{
+    LEVEL_STEP, MAX_LEVEL, MAX_SMOOTHNESS, MIN_LEVEL, SMOOTHNESS_STEP, TILE_FLOOR_GAP, Theme,
+};
+use maze::thumbnail::render_thumbnail;
+use maze::tile::Tile;
+use maze::utils::Vec2d;
 
 use macroquad::prelude::*;
+use macroquad::rand::RandGenerator;
 use std::collections::HashSet;
 
-#[macroquad::main("Maze")]
-async fn main() {
-    let time = macroquad::miniquad::date::now();
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GameState {
+    Generating,
+    // Picking a custom start and exit before playing
+    Setup,
+    Playing,
+    Won,
+    // Ran out of time in race mode
+    Lost,
+    // Picking one of the `--levels` mazes by its thumbnail
+    LevelSelect,
+}
+
+// Range of the [ / ] game speed multiplier
+const MIN_TIME_SCALE: f32 = 0.25;
+const MAX_TIME_SCALE: f32 = 4.0;
+
+// Default `--mercy` wait on one tile before the solution fades in, and the
+// seconds the fade takes
+const MERCY_SECONDS: f32 = 15.0;
+const MERCY_FADE_SECONDS: f32 = 1.0;
+
+// Pixels per tile in level select thumbnails
+const THUMBNAIL_TILE_PX: u16 = 4;
+
+fn draw_hud(
+    player: &Player,
+    tiles: &Vec2d<Tile>,
+    stats: &Stats,
+    practice: bool,
+    time_limit: Option<f32>,
+) {
+    let best = match (practice, stats.best_time) {
+        (true, _) => "Best: - (practice)".to_string(),
+        (false, Some(best)) => format!("Best: {:.1}s", best),
+        (false, None) => "Best: -".to_string(),
+    };
+    let mut lines = vec![
+        format!("Tile: ({}, {})", player.tile_pos.0, player.tile_pos.1),
+        format!("Maze: {} x {}", tiles.cols, tiles.rows),
+        format!("Steps: {}", stats.steps),
+        format!("Time: {:.1}s", stats.elapsed),
+        best,
+    ];
+    if let Some(remaining) = stats.remaining(time_limit) {
+        lines.push(format!("Left: {:.1}s", remaining));
+    }
+    for (i, split) in stats.splits.iter().enumerate() {
+        lines.push(format!("Split {}: {:.1}s", i + 1, split));
+    }
+    if player.trap_hits > 0 {
+        lines.push(format!("Traps hit: {}", player.trap_hits));
+    }
+
+    draw_panel(&lines, false);
+}
+
+// Text lines on a dark box in the top left corner, or the bottom left one
+fn draw_panel(lines: &[String], bottom: bool) {
+    // Scale with the window so the panel survives resizes
+    let font_size = (screen_height() * 0.03).max(14.0);
+    let line_height = font_size * 1.1;
+    let padding = font_size * 0.5;
+    let height = line_height * lines.len() as f32 + padding * 2.0;
+    let x = screen_width() * 0.01;
+    let y = if bottom {
+        screen_height() * 0.99 - height
+    } else {
+        screen_height() * 0.01
+    };
+
+    let mut width: f32 = 0.0;
+    for line in lines {
+        width = width.max(measure_text(line, None, font_size as u16, 1.0).width);
+    }
+
+    draw_rectangle(
+        x,
+        y,
+        width + padding * 2.0,
+        height,
+        Color::new(0.0, 0.0, 0.0, 0.6),
+    );
+
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(
+            line,
+            x + padding,
+            y + padding + line_height * (i as f32 + 0.8),
+            font_size,
+            WHITE,
+        );
+    }
+}
+
+// Size of `--export-batch` mazes unless `--cols` / `--rows` are given
+const BATCH_COLS: usize = 30;
+const BATCH_ROWS: usize = 20;
 
-    println!("Rand seed: {}", time);
-    rand::srand(time as _);
+fn main() {
+    // `--config PATH` can only come from the command line
+    let cli = Config::default().with_args(std::env::args().skip(1));
+    let path = cli.value("config").unwrap_or(CONFIG_FILE);
+    let config = Config::load(std::path::Path::new(path)).with_args(std::env::args().skip(1));
+
+    // `--export-batch DIR` writes mazes to disk without opening a window
+    if let Some(dir) = config.value("export-batch") {
+        let number = |name: &str, default: u64| config.parsed(name).unwrap_or(default);
+        let written = export_batch(
+            std::path::Path::new(dir),
+            (number("cols", BATCH_COLS as u64) as usize).max(2),
+            (number("rows", BATCH_ROWS as u64) as usize).max(2),
+            number("min-solution-len", MIN_SOLUTION_LEN as u64) as usize,
+            number("count", 1),
+            number("seed", macroquad::miniquad::date::now() as u64),
+            config.flag("overwrite"),
+        )
+        .unwrap_or_else(|err| {
+            eprintln!("Can't export to {}: {}", dir, err);
+            std::process::exit(1);
+        });
+        println!("Wrote {} files to {}", written.len(), dir);
+        return;
+    }
+    macroquad::Window::new("Maze", game(config));
+}
+
+async fn game(config: Config) {
+    let time = macroquad::miniquad::date::now();
+    // `--daily` seeds from today's UTC date and plays on a fixed config, so
+    // everyone gets the same maze
+    let daily = config.flag("daily");
+    let settings = Settings {
+        perfect: !daily && config.flag("perfect"),
+        border_exit: !daily && config.flag("border-exit"),
+        random_spawn: !daily && config.flag("random-spawn"),
+        min_solution_len: config
+            .parsed("min-solution-len")
+            .filter(|_| !daily)
+            .unwrap_or(MIN_SOLUTION_LEN),
+        ..Settings::default()
+    };
+    let seed = if daily {
+        let (year, month, day) = utc_date(time);
+        let seed = daily_seed((year, month, day));
+        println!(
+            "Daily maze {:04}-{:02}-{:02}, seed: {}",
+            year, month, day, seed
+        );
+        seed
+    } else {
+        println!("Rand seed: {}", time);
+        time as u64
+    };
+    rand::srand(seed);
 
     // Load shader files
     let vertex_shader = include_str!("shaders/vertex.glsl");
     let fragment_shader = include_str!("shaders/border.glsl");
 
-    // Create a single material with our unified shader
+    // Create a single material with our unified shader, tiles are drawn
+    // without it if it doesn't compile on this backend
     let tile_material = load_material(
         ShaderSource::Glsl {
             vertex: vertex_shader,
@@ -34,103 +208,1115 @@ async fn main() {
                 UniformDesc::new("border_side", UniformType::Int1),
                 UniformDesc::new("tile_color", UniformType::Float4),
                 UniformDesc::new("border_color", UniformType::Float4),
+                UniformDesc::new("smoothness", UniformType::Float1),
+                UniformDesc::new("brightness", UniformType::Float1),
+                UniformDesc::new("contrast", UniformType::Float1),
             ],
             ..Default::default()
         },
     )
-    .unwrap();
+    .map_err(|err| eprintln!("Tile shader unavailable, drawing plain tiles: {}", err))
+    .ok();
 
-    let mut tiles = generate_tiles();
-    let tiles_len = tiles.vec.len();
+    // Fixed tile size in pixels, mazes bigger than the screen scroll with the player
+    let fixed_tile_px: Option<f32> = config.parsed("tile-px");
+
+    // A maze file given with `--load` is played instead of carving a new one
+    let loaded = config.value("load").map(|path| {
+        let text = std::fs::read_to_string(path).unwrap_or_else(|err| {
+            eprintln!("Can't read {}: {}", path, err);
+            std::process::exit(1);
+        });
+        let mut tiles = maze_from_string(&text).unwrap_or_else(|err| {
+            eprintln!("Can't load {}: {}", path, err);
+            std::process::exit(1);
+        });
+        // Checked as written, before anything could pick an exit for it
+        if let Err(err) = solver::validate_playable(&tiles) {
+            eprintln!("Can't play {}: {}", path, err);
+            std::process::exit(1);
+        }
+        fit_tiles_to_screen(&mut tiles, screen_width(), screen_height());
+        tiles
+    });
+
+    let is_loaded = loaded.is_some();
+    // `--letterbox` keeps whole square tiles centered with even margins
+    let letterbox = config.flag("letterbox");
+    // Corridors this many tiles wide (`--thick N`), carved in one go
+    let thick: Option<usize> = config.parsed("thick").filter(|&width| !daily && width > 1);
+    let new_tiles = || {
+        if daily {
+            let mut tiles = blank_tiles(DAILY_COLS, DAILY_ROWS);
+            fit_tiles_to_screen(&mut tiles, screen_width(), screen_height());
+            tiles
+        } else if let Some(width) = thick {
+            let layout = generate_tiles(fixed_tile_px);
+            let logical = |len: usize| (len / width).max(2);
+            let mut tiles = generate_thick(logical(layout.cols), logical(layout.rows), width);
+            fit_tiles_to_screen(&mut tiles, screen_width(), screen_height());
+            tiles
+        } else if letterbox && fixed_tile_px.is_none() {
+            generate_tiles_letterboxed()
+        } else {
+            generate_tiles(fixed_tile_px)
+        }
+    };
+    let mut tiles = loaded.unwrap_or_else(new_tiles);
+    // Toroidal mazes, walking off an edge comes back on the opposite one
+    let wrap = !daily && thick.is_none() && config.flag("wrap");
+    if !is_loaded {
+        tiles.wrap = wrap;
+    }
 
     println!("cols: {}", tiles.cols);
     println!("rows: {}", tiles.rows);
     println!("tiles: {}", tiles.vec.len());
 
+    // Exact number of loops instead of a random share of walls (`--loops N`)
+    let target_loops: Option<usize> = config.parsed("loops").filter(|_| !daily);
+    // Open chambers carved before the backtracker runs (`--rooms N`)
+    let room_count: usize = config
+        .parsed("rooms")
+        .filter(|_| !daily && thick.is_none())
+        .unwrap_or(0);
+    // Walls of the perfect maze before loops were added, empty for loaded mazes
+    let mut base_walls: Vec<u8> = Vec::new();
+    // Walls from before B braided the maze, restored by pressing B again
+    let mut unbraided_walls: Option<Vec<u8>> = None;
+    let mut rooms = if is_loaded {
+        Vec::new()
+    } else {
+        carve_rooms(&mut tiles, room_count, ROOM_MIN_SIZE, ROOM_MAX_SIZE)
+    };
+
     let mut visited: HashSet<(usize, usize)> = HashSet::new();
     let mut stack: Vec<(usize, usize)> = Vec::new();
+    // Tile the generator carves from next, unrelated to where players spawn
+    let mut carve_head = prepare_carving(&tiles, &rooms, &mut visited, &mut stack);
+    let mut run_time: f64 = settings.carve_interval;
+    let step_gen = config.flag("step-gen");
+    // `--instant` carves as fast as a frame budget allows instead of animating
+    let instant = config.flag("instant");
+    // Saved mazes in `--levels DIR` and `--pack FILE`, picked from a row of
+    // thumbnails. Pack mazes are named after the file and their place in it
+    let pack = config.value("pack").map(|file| {
+        let path = std::path::Path::new(file);
+        let mazes = load_pack(path).unwrap_or_else(|err| {
+            eprintln!("Can't load {}: {}", file, err);
+            std::process::exit(1);
+        });
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        mazes
+            .into_iter()
+            .enumerate()
+            .map(|(i, maze)| (format!("{} {}", stem, i + 1), maze))
+            .collect::<Vec<_>>()
+    });
+    let levels: Vec<(String, Vec2d<Tile>, Texture2D)> = config
+        .value("levels")
+        .map(|dir| {
+            load_level_dir(std::path::Path::new(dir)).unwrap_or_else(|err| {
+                eprintln!("Can't read {}: {}", dir, err);
+                std::process::exit(1);
+            })
+        })
+        .unwrap_or_default()
+        .into_iter()
+        .chain(pack.unwrap_or_default())
+        .map(|(name, level)| {
+            let thumbnail = Texture2D::from_image(&render_thumbnail(&level, THUMBNAIL_TILE_PX));
+            thumbnail.set_filter(FilterMode::Nearest);
+            (name, level, thumbnail)
+        })
+        .collect();
+    let mut selected_level = 0;
+    // Maze picked in level select, swapped in at the end of the frame
+    let mut load_level: Option<Vec2d<Tile>> = None;
+    let mut state = if !levels.is_empty() {
+        GameState::LevelSelect
+    } else if is_loaded {
+        GameState::Playing
+    } else {
+        GameState::Generating
+    };
 
-    let start_row = rand::gen_range(0, tiles.rows);
-    let start_col = rand::gen_range(0, tiles.cols);
-    let mut start_position = (start_col, start_row);
-    let max_steps = NUMBER_OF_TILES_IN_BIGGER_AXIS / 10;
-
-    let interval = 0.1 / (NUMBER_OF_TILES_IN_BIGGER_AXIS as f64);
-    let mut run_time: f64 = interval;
-    let mut generation_done = false;
-
-    let first_tile = tiles.vec.get(0).unwrap();
-    let first_tile_pos = first_tile.screen_position.clone();
-    let mut player: Player = Player::new(
-        0,
-        0,
-        first_tile.width,
-        first_tile.screen_position.x + (0 as f32 * first_tile.width) + (first_tile.width / 2.0),
-        first_tile.screen_position.y + (0 as f32 * first_tile.width) + (first_tile.width / 2.0),
-    );
+    // `--animate-walls` slides carved walls open
+    let mut theme = Theme {
+        animate_walls: config.flag("animate-walls"),
+        ..Theme::default()
+    };
+    let mut high_contrast = false;
+    // Classic solid wall blocks instead of the border shader
+    let mut block_style = false;
+    let mut first_tile_pos = tiles.vec.first().unwrap().screen_position;
+    // `--two-player` races a second player (WASD) against the first (arrows)
+    let player_count = if config.flag("two-player") { 2 } else { 1 };
+    let key_bindings = bindings_for(player_count);
+    // Ramp up to full speed over `--acceleration` tiles/s², instant by default
+    let acceleration: f32 = config
+        .parsed("acceleration")
+        .filter(|&value: &f32| value > 0.0)
+        .unwrap_or(f32::INFINITY);
+    // Tile with its own border opening the players spawn on, generated mazes
+    // mark it once carving is done
+    let mut entrance = entrance_of(&tiles).unwrap_or((0, 0));
+    if is_loaded {
+        mark_entrance(&mut tiles, entrance);
+    }
+    // Player 1 drives the camera, HUD, autopilot and best times
+    let mut players = spawn_players(&tiles, entrance, &theme, player_count, acceleration);
+    let mut winner = 0;
+    // Player 1's optimal vs actual path length, shown on the win screen
+    let mut win_summary: Option<String> = None;
+    // Replays of the same maze reuse its solved length
+    let mut solution = solver::SolutionCache::new();
 
     // Create control pad
-    let control_size = screen_height() * 0.25;
-    let controls_x = screen_width() - control_size - 20.0;
-    let controls_y = screen_height() - control_size - 20.0;
-    let mut control_pad = ControlPad::new(controls_x, controls_y, control_size);
+    let pad = settings.control_pad_rect(screen_width(), screen_height());
+    let mut control_pad = ControlPad::new(pad.x, pad.y, pad.w);
+    // Touch screens have no N key, so a new maze button sits above the pad
+    let button_height = pad.w / 5.0;
+    let mut new_maze_button = LabelButton::new(
+        pad.x,
+        pad.y - button_height - 10.0,
+        pad.w,
+        button_height,
+        "New maze",
+    );
+
+    let mut stats = Stats::new();
+    // Last clean winning run, raced as a ghost whenever the same walls come up
+    // again (R after a win, or a re-seeded maze)
+    let mut ghost: Option<(Vec<u8>, Recording)> = None;
+    // Practice mode always shows a faint guide along the solution
+    let mut practice = config.flag("practice");
+    let mut practice_path: Option<Vec<(usize, usize)>> = None;
+    // Z watches A* search the maze, one expansion per press of '.'
+    let mut solver_view: Option<solver::SolverState> = None;
+    let mut show_hud = true;
+    let mut show_stack = false;
+    // Some(with breadcrumbs) while the minimap is shown
+    let mut minimap_view: Option<bool> = None;
+    let mut minimap = Minimap::new();
+    // FPS readout is a debugging aid, shown by default only in debug builds
+    let mut show_fps = cfg!(debug_assertions);
+    let mut fps_counter = FpsCounter::new();
+    // Loaded mazes were refused without an exit, generated ones get theirs later
+    let mut exit_pos = tiles
+        .vec
+        .iter()
+        .find(|tile| tile.exit)
+        .map_or((0, 0), |exit| (exit.col, exit.row));
+    // Race mode: reach the exit before a countdown scaled to the solution runs out
+    let race = config.flag("race");
+    let race_limit = |tiles: &Vec2d<Tile>, start, exit| {
+        if !race {
+            return None;
+        }
+        let solution = solver::solve_astar(tiles, start, exit)?;
+        Some(time_limit_for(solution.len() - 1, PLAYER_TILES_PER_SECOND))
+    };
+    let mut time_limit = if is_loaded {
+        race_limit(&tiles, entrance, exit_pos)
+    } else {
+        None
+    };
+    let mut peek = Peek::new();
+    let mut show_dead_ends = false;
+    let mut show_coords = false;
+    let mut show_adjacency = false;
+    let mut show_explore_hint = false;
+    // Tab counts the steps left on the best route, the route itself stays
+    // hidden. Not in a race or against another player
+    let steps_hint_allowed = !race && player_count == 1;
+    let mut show_steps_hint = false;
+    let mut steps_hint: Option<usize> = None;
+    // Slow motion / turbo multiplier on the dt fed to movement and the timer
+    let mut time_scale: f32 = 1.0;
+    // Walking the solution on its own, used_autopilot rules out a best time
+    let mut autopilot = false;
+    let mut used_autopilot = false;
+    // `--mercy [SECONDS]` fades in the solution once the player is stuck on a
+    // tile that long, never in a race. A shown solution rules out a best time
+    let mercy_after: Option<f32> = (config.flag("mercy") && !race && player_count == 1)
+        .then(|| config.parsed("mercy").unwrap_or(MERCY_SECONDS));
+    let mut mercy_alpha: f32 = 0.0;
+    let mut used_mercy = false;
+    let mut flood: Option<FloodReveal> = None;
+    let mut particles = Particles::new();
+    let mut show_grid = false;
+    let mut teleports = Vec::new();
+    // Hard mode hides tiles that send a player back to their spawn
+    let mut traps: HashSet<(usize, usize)> = HashSet::new();
+    // `--checkpoints N` tiles along the solution, each records a split when
+    // reached in order
+    let checkpoint_count: usize = config.parsed("checkpoints").unwrap_or(0);
+    let mut checkpoints: Vec<(usize, usize)> = Vec::new();
+    let mut camera = MazeCamera::new(players[0].screen_pos);
+    let mut was_moving = false;
+    let difficulty = config
+        .value("difficulty")
+        .filter(|_| !daily)
+        .and_then(Difficulty::parse)
+        .unwrap_or_default();
+    let mut easy_regenerations = 0;
+    let algorithm = config
+        .value("algorithm")
+        .filter(|_| !daily)
+        .and_then(Algorithm::parse)
+        .unwrap_or_default();
+    // I shows how the current maze was made, gathered again whenever it changes
+    let mut show_info = false;
+    let mut maze_info = if is_loaded {
+        MazeInfo::gather(&tiles, None, seed, entrance, exit_pos)
+    } else {
+        MazeInfo::default()
+    };
+    // With `--setup` every maze starts by picking its start and exit
+    let setup_first = config.flag("setup");
+    let mut setup = Setup::new();
+    let mut setup_error: Option<SetupError> = None;
+    // Set to start over with a fresh maze at the end of the frame
+    let mut regenerate = false;
+    // `--fps-cap N` sleeps off the rest of each frame, uncapped by default
+    let fps_cap: Option<u32> = config.value("fps-cap").map(|value| match value.parse() {
+        Ok(cap) if cap > 0 => cap,
+        _ => {
+            eprintln!("--fps-cap needs a frame rate above 0, got {}", value);
+            std::process::exit(1);
+        }
+    });
 
     loop {
-        clear_background(BLACK);
+        let frame_start = macroquad::miniquad::date::now();
+        clear_background(theme.background);
         let dt = get_frame_time();
 
-        if !generation_done {
+        if state == GameState::Generating {
             let seconds_passed = get_time();
 
-            if seconds_passed >= run_time && visited.len() != tiles_len {
-                start_position = iterative_backtracking(
+            // Kruskal isn't animated, carve everything on the first frame. Thick
+            // mazes come out of new_tiles already carved
+            if visited.len() != tiles.vec.len() {
+                if thick.is_some() {
+                    visited.extend((0..tiles.vec.len()).map(|i| tiles.coords(i)));
+                } else if algorithm == Algorithm::Kruskal {
+                    kruskal(&mut tiles);
+                    visited.extend((0..tiles.vec.len()).map(|i| tiles.coords(i)));
+                }
+            }
+
+            // With `--step-gen` each Space press carves a single step
+            let advance = if step_gen {
+                is_key_pressed(KeyCode::Space)
+            } else {
+                seconds_passed >= run_time
+            };
+
+            // Enter (or Space unless stepping) skips the rest of the animation
+            let skip =
+                is_key_pressed(KeyCode::Enter) || (!step_gen && is_key_pressed(KeyCode::Space));
+
+            if skip && visited.len() != tiles.vec.len() {
+                carve_head =
+                    iterative_backtracking(&mut tiles, &mut visited, &mut stack, carve_head, 0);
+            } else if instant && !step_gen && visited.len() != tiles.vec.len() {
+                carve_head = generate_with_budget(
                     &mut tiles,
                     &mut visited,
                     &mut stack,
-                    start_position,
-                    max_steps as _,
+                    carve_head,
+                    settings.generation_budget_ms,
+                    macroquad::miniquad::date::now,
                 );
-                run_time = seconds_passed + interval;
-            } else if visited.len() == tiles_len {
-                let precentage = rand::gen_range(0.01, 0.05);
-                remove_random_walls(&mut tiles, precentage);
-                choose_exit_tile(&mut tiles);
-                generation_done = true;
-                println!("Maze generation done!")
+            } else if advance && visited.len() != tiles.vec.len() {
+                let steps = if step_gen { 1 } else { settings.carve_steps };
+                carve_head =
+                    iterative_backtracking(&mut tiles, &mut visited, &mut stack, carve_head, steps);
+                run_time = seconds_passed + settings.carve_interval;
+                if step_gen {
+                    println!("head: {:?}, stack: {:?}", carve_head, stack);
+                }
+            }
+            if visited.len() == tiles.vec.len() {
+                // Players move to the new entrance unless they already stand on it
+                (base_walls, entrance, exit_pos) =
+                    finish_generation(&mut tiles, !rooms.is_empty(), target_loops, &settings);
+                if entrance != players[0].tile_pos {
+                    players = spawn_players(&tiles, entrance, &theme, player_count, acceleration);
+                }
+
+                if is_too_easy(&tiles, entrance, exit_pos, difficulty)
+                    && easy_regenerations < MAX_EASY_REGENERATIONS
+                {
+                    easy_regenerations += 1;
+                    println!(
+                        "Maze too easy for {:?}, regenerating ({}/{})",
+                        difficulty, easy_regenerations, MAX_EASY_REGENERATIONS
+                    );
+                    regenerate = true;
+                } else {
+                    easy_regenerations = 0;
+                    println!(
+                        "Shortest paths to the exit: {}",
+                        solver::count_shortest_paths(&tiles, entrance, exit_pos)
+                    );
+                    time_limit = race_limit(&tiles, entrance, exit_pos);
+                    teleports = place_teleports(&tiles, TELEPORT_PAIRS, &[entrance, exit_pos]);
+                    traps = place_traps(
+                        &tiles,
+                        difficulty.traps(),
+                        entrance,
+                        exit_pos,
+                        &teleport_pads(&teleports),
+                    );
+                    checkpoints = place_checkpoints(
+                        &solver::solve_astar(&tiles, entrance, exit_pos).unwrap_or_default(),
+                        checkpoint_count,
+                        &teleport_pads(&teleports),
+                    );
+                    maze_info = MazeInfo::gather(&tiles, Some(algorithm), seed, entrance, exit_pos);
+                    state = if setup_first {
+                        GameState::Setup
+                    } else {
+                        GameState::Playing
+                    };
+                    println!("Maze generation done!")
+                }
             }
         }
 
-        for tile in &tiles.vec {
-            tile.draw(&tile_material);
+        if is_key_pressed(KeyCode::K) {
+            theme.next_player_color();
+            for (i, player) in players.iter_mut().enumerate() {
+                player.color = theme.player_color(i);
+            }
+        }
+        // T swaps in the high contrast palette, keeping the look settings
+        if is_key_pressed(KeyCode::T) {
+            high_contrast = !high_contrast;
+            let palette = if high_contrast {
+                Theme::high_contrast()
+            } else {
+                Theme::default()
+            };
+            theme = Theme {
+                gap: theme.gap,
+                smoothness: theme.smoothness,
+                brightness: theme.brightness,
+                contrast: theme.contrast,
+                player_color: theme.player_color,
+                animate_walls: theme.animate_walls,
+                ..palette
+            };
+        }
+        // [ and ] halve or double the game speed (movement and timer)
+        if is_key_pressed(KeyCode::LeftBracket) || is_key_pressed(KeyCode::RightBracket) {
+            let factor = if is_key_pressed(KeyCode::RightBracket) {
+                2.0
+            } else {
+                0.5
+            };
+            time_scale = (time_scale * factor).clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+            println!("Time scale: {}x", time_scale);
+        }
+        // J prints the maze as JSON for other tools
+        if is_key_pressed(KeyCode::J) {
+            println!("{}", to_json(&tiles, players[0].tile_pos));
+        }
+        // F5 benchmarks every algorithm on a maze of the current size
+        if is_key_pressed(KeyCode::F5) {
+            let rng = RandGenerator::new();
+            rng.srand(macroquad::miniquad::date::now() as u64);
+            print_comparison(&compare_algorithms(tiles.cols, tiles.rows, &rng));
+        }
+        // +/- soften or sharpen the wall edges
+        if is_key_pressed(KeyCode::Equal) || is_key_pressed(KeyCode::KpAdd) {
+            theme.smoothness = (theme.smoothness + SMOOTHNESS_STEP).min(MAX_SMOOTHNESS);
+        }
+        if is_key_pressed(KeyCode::Minus) || is_key_pressed(KeyCode::KpSubtract) {
+            theme.smoothness = (theme.smoothness - SMOOTHNESS_STEP).max(0.0);
+        }
+        // F6 / F7 darken or brighten the tiles, F8 / F9 lower or raise the contrast
+        let level_step = |key_down, key_up| {
+            if is_key_pressed(key_up) {
+                LEVEL_STEP
+            } else if is_key_pressed(key_down) {
+                -LEVEL_STEP
+            } else {
+                0.0
+            }
+        };
+        theme.brightness =
+            (theme.brightness + level_step(KeyCode::F6, KeyCode::F7)).clamp(MIN_LEVEL, MAX_LEVEL);
+        theme.contrast =
+            (theme.contrast + level_step(KeyCode::F8, KeyCode::F9)).clamp(MIN_LEVEL, MAX_LEVEL);
+        if is_key_pressed(KeyCode::G) {
+            theme.gap = if theme.gap > 0.0 { 0.0 } else { TILE_FLOOR_GAP };
+        }
+
+        if is_key_pressed(KeyCode::Key0) || is_key_pressed(KeyCode::Kp0) {
+            camera.fit(fit_camera(&tiles));
+        }
+        // Starting to move again goes back to following the player
+        let moving = players[0].current_direction != Direction::None;
+        if moving && !was_moving {
+            camera.release_fit();
+        }
+        was_moving = moving;
+        camera.follow(players[0].screen_pos, maze_bounds(&tiles));
+        set_camera(&camera.to_camera2d());
+
+        if is_key_pressed(KeyCode::L) {
+            show_grid = !show_grid;
+        }
+        if show_grid {
+            overlay::draw_background_grid(&tiles, theme.grid_color);
+        }
+
+        if is_key_pressed(KeyCode::V) {
+            block_style = !block_style;
+        }
+        // Freshly carved tiles fade into the floor
+        for tile in &mut tiles.vec {
+            tile.update_transition(dt);
+        }
+        if block_style {
+            draw_block_style(&tiles, &theme);
+        } else {
+            for tile in &tiles.vec {
+                match &tile_material {
+                    Some(material) => tile.draw(material, &theme),
+                    None => tile.draw_plain(&theme),
+                }
+            }
         }
 
         // Reset to default material
         gl_use_default_material();
+        if theme.animate_walls && !block_style {
+            for tile in &tiles.vec {
+                tile.draw_openings(&theme);
+            }
+        }
+
+        if theme.exit_marker
+            && let Some(exit) = tiles.vec.iter().find(|tile| tile.exit)
+        {
+            overlay::draw_tile_marker(&tiles, (exit.col, exit.row), theme.background);
+        }
+
+        if is_key_pressed(KeyCode::E) {
+            show_dead_ends = !show_dead_ends;
+        }
+        if show_dead_ends {
+            overlay::tint_tiles(&tiles, &dead_ends(&tiles), Color::new(0.8, 0.1, 0.1, 0.4));
+        }
+
+        // Debug builds only, F3 labels every tile with its grid position
+        if cfg!(debug_assertions) && is_key_pressed(KeyCode::F3) {
+            show_coords = !show_coords;
+        }
+        if show_coords {
+            overlay::draw_tile_coords(&tiles);
+        }
+        // Also debug only, F4 draws a line across every open passage
+        if cfg!(debug_assertions) && is_key_pressed(KeyCode::F4) {
+            show_adjacency = !show_adjacency;
+        }
+        if show_adjacency {
+            overlay::draw_adjacency(&tiles, Color::new(0.0, 1.0, 0.5, 0.8));
+        }
+
+        // Y shows the backtracker's stack on top of the explored region
+        if is_key_pressed(KeyCode::Y) {
+            show_stack = !show_stack;
+        }
+        // Shade the explored region and the carving head while the backtracker
+        // is still running
+        let explored = overlay::explored_shading(&visited, tiles.vec.len());
+        if state == GameState::Generating && !explored.is_empty() {
+            overlay::tint_tiles(&tiles, &explored, theme.visited_color);
+            if show_stack {
+                let snake = overlay::stack_tiles(&stack, carve_head);
+                overlay::tint_tiles(&tiles, &snake, theme.stack_color);
+            }
+            overlay::tint_tiles(&tiles, &[carve_head], theme.carve_head_color);
+        }
+
+        if is_key_pressed(KeyCode::H) {
+            show_hud = !show_hud;
+        }
 
-        if generation_done {
+        if is_key_pressed(KeyCode::M) {
+            // Off, maze only, maze with breadcrumbs
+            minimap_view = match minimap_view {
+                None => Some(false),
+                Some(false) => Some(true),
+                Some(true) => None,
+            };
+        }
+
+        if state == GameState::Playing {
+            // Solution currently on screen, repeated on the minimap
+            let mut shown_solution = None;
             // Handle keyboard input as an alternative to on-screen buttons
-            control_pad.update(&mut player);
-            player.draw();
+            control_pad.update(&mut players, &key_bindings);
+
+            if is_key_pressed(KeyCode::P) {
+                practice = !practice;
+            }
+            if is_key_pressed(KeyCode::C) {
+                state = GameState::Setup;
+            }
+            // X re-rolls the extra loops on the same carved maze
+            if is_key_pressed(KeyCode::X) && !base_walls.is_empty() {
+                unbraided_walls = None;
+                // The snapshot predates the entrance and border exit openings
+                let exit_open = border_walls(&tiles, exit_pos)
+                    .iter()
+                    .any(|wall| !tiles.index(exit_pos.0, exit_pos.1).walls().contains(wall));
+                restore_walls(&mut tiles, &base_walls);
+                add_loops(&mut tiles, entrance, target_loops, &settings);
+                mark_entrance(&mut tiles, entrance);
+                if exit_open {
+                    open_border(&mut tiles, exit_pos);
+                }
+                maze_info = MazeInfo::gather(&tiles, maze_info.algorithm, seed, entrance, exit_pos);
+                practice_path = None;
+                solver_view = None;
+            }
+            // B flips between this maze and a braided copy with fewer dead ends
+            if is_key_pressed(KeyCode::B) {
+                match unbraided_walls.take() {
+                    Some(walls) => restore_walls(&mut tiles, &walls),
+                    None => {
+                        unbraided_walls = Some(snapshot_walls(&tiles));
+                        let opened = braid(&mut tiles, BRAID_SHARE);
+                        println!(
+                            "Braided {} dead ends, loops: {}",
+                            opened,
+                            count_loops(&tiles)
+                        );
+                    }
+                }
+                assert_wall_consistency(&tiles);
+                maze_info = MazeInfo::gather(&tiles, maze_info.algorithm, seed, entrance, exit_pos);
+                practice_path = None;
+                solver_view = None;
+            }
+            // O walks the solution, any manual input takes back control
+            if is_key_pressed(KeyCode::O) {
+                autopilot = !autopilot;
+                if autopilot {
+                    let path = solver::solve_astar(&tiles, players[0].tile_pos, exit_pos)
+                        .unwrap_or_default();
+                    players[0].follow(solver::path_directions(&tiles, &path));
+                    used_autopilot = true;
+                } else {
+                    players[0].set_direction(Direction::None);
+                }
+            }
+            if practice {
+                // Solved once per maze, from the player's tile when first needed
+                let path = practice_path.get_or_insert_with(|| {
+                    solver::practice_guide(&tiles, players[0].tile_pos, exit_pos)
+                });
+                overlay::draw_path(&tiles, path, Color::new(1.0, 1.0, 1.0, 0.15));
+                shown_solution = Some(path.clone());
+            }
+
+            if is_key_pressed(KeyCode::Z) {
+                solver_view = match solver_view {
+                    Some(_) => None,
+                    None => Some(solver::SolverState::new(
+                        &tiles,
+                        players[0].tile_pos,
+                        exit_pos,
+                    )),
+                };
+            }
+            if let Some(view) = &mut solver_view {
+                if is_key_pressed(KeyCode::Period) {
+                    view.step(&tiles);
+                }
+                let closed: Vec<(usize, usize)> = view.closed.iter().copied().collect();
+                overlay::tint_tiles(&tiles, &closed, Color::new(0.3, 0.5, 1.0, 0.35));
+                overlay::tint_tiles(&tiles, &view.frontier(), Color::new(1.0, 0.6, 0.0, 0.5));
+                if let Some(path) = &view.path {
+                    overlay::draw_path(&tiles, path, Color::new(1.0, 1.0, 1.0, 0.5));
+                }
+            }
+
+            // U points the way to the closest tile not yet walked on
+            if is_key_pressed(KeyCode::U) {
+                show_explore_hint = !show_explore_hint;
+            }
+            if show_explore_hint
+                && let Some(path) =
+                    solver::path_to_unexplored(&tiles, players[0].tile_pos, &stats.explored)
+                && let Some(&direction) = solver::path_directions(&tiles, &path).first()
+            {
+                overlay::draw_hint_arrow(&tiles, players[0].tile_pos, direction, WHITE);
+            }
+
+            if is_key_pressed(KeyCode::Tab) && steps_hint_allowed {
+                show_steps_hint = !show_steps_hint;
+            }
+            if show_steps_hint {
+                steps_hint = solver::steps_to_go(&tiles, players[0].tile_pos, exit_pos);
+            }
+
+            if let Some(threshold) = mercy_after {
+                let target: f32 = if stats.is_stuck(threshold) { 1.0 } else { 0.0 };
+                let step = dt / MERCY_FADE_SECONDS;
+                mercy_alpha = target.clamp(mercy_alpha - step, mercy_alpha + step);
+                if mercy_alpha > 0.0
+                    && let Some(path) = solver::solve_astar(&tiles, players[0].tile_pos, exit_pos)
+                {
+                    used_mercy = true;
+                    overlay::draw_path(&tiles, &path, Color::new(1.0, 1.0, 1.0, 0.5 * mercy_alpha));
+                    shown_solution = Some(path);
+                }
+            }
+
+            // Hold Q to briefly reveal the solution
+            let cooldown = peek_cooldown(tiles.cols, tiles.rows);
+            if peek.update(is_key_down(KeyCode::Q), get_time(), cooldown)
+                && let Some(path) = solver::solve_astar(&tiles, players[0].tile_pos, exit_pos)
+            {
+                overlay::draw_path(&tiles, &path, Color::new(1.0, 1.0, 1.0, 0.5));
+                shown_solution = Some(path);
+            }
+
+            overlay::draw_teleports(&tiles, &teleports);
+            overlay::draw_traps(&tiles, &traps, theme.trap_color);
+            for &checkpoint in checkpoints.iter().skip(stats.splits.len()) {
+                overlay::draw_tile_marker(&tiles, checkpoint, theme.checkpoint_color);
+            }
+            if let Some((walls, run)) = &ghost
+                && *walls == snapshot_walls(&tiles)
+                && let Some(pos) = run.position_at(stats.elapsed)
+            {
+                let mut color = players[0].color;
+                color.a = 0.35;
+                draw_circle(pos.x, pos.y, players[0].radius, color);
+            }
+            for player in &players {
+                player.draw();
+            }
+
+            set_default_camera();
             control_pad.draw();
-            if player.update(dt, &tiles, first_tile_pos.x, first_tile_pos.y) {
-                generation_done = false;
-                tiles = generate_tiles();
-                visited.clear();
-                stack.clear();
-                let start_row = rand::gen_range(0, tiles.rows);
-                let start_col = rand::gen_range(0, tiles.cols);
-                start_position = (start_col, start_row);
-                player = Player::new(
-                    player.tile_pos.0,
-                    player.tile_pos.1,
-                    player.tile_size,
-                    player.screen_pos.x,
-                    player.screen_pos.y,
-                )
-            }
-        }
-
-        draw_fps();
+            new_maze_button.draw();
+            if new_maze_button.update() {
+                regenerate = true;
+            }
+            if let Some(breadcrumbs) = minimap_view {
+                minimap.draw(
+                    &tiles,
+                    &theme,
+                    players[0].tile_pos,
+                    breadcrumbs.then_some(&stats.explored),
+                    shown_solution.as_deref(),
+                );
+            }
+            // Walls stop each player on their own, players pass through each other
+            let mut found_exit = None;
+            for (i, player) in players.iter_mut().enumerate() {
+                if player.update(
+                    dt * time_scale,
+                    &tiles,
+                    &teleports,
+                    &traps,
+                    first_tile_pos.x,
+                    first_tile_pos.y,
+                ) {
+                    found_exit = found_exit.or(Some(i));
+                }
+            }
+            stats.update(dt * time_scale, players[0].tile_pos);
+            stats.record_split(&checkpoints, players[0].tile_pos);
+            stats.run.record(stats.elapsed, players[0].screen_pos);
+            if players[0].queued.is_empty() {
+                autopilot = false;
+            }
+            if show_hud {
+                draw_hud(&players[0], &tiles, &stats, practice, time_limit);
+            }
+            if !beat_the_clock(time_limit, stats.elapsed) {
+                println!("Time's up after {:.1}s", stats.elapsed);
+                state = GameState::Lost;
+            } else if let Some(first) = found_exit {
+                winner = first;
+                // Any use of the guide on this maze rules out a best time
+                if winner == 0 {
+                    let assisted =
+                        practice || practice_path.is_some() || used_autopilot || used_mercy;
+                    stats.record_win(assisted);
+                    if !assisted {
+                        ghost = Some((snapshot_walls(&tiles), stats.run.clone()));
+                    }
+                    let optimal = solution.solution_len(&tiles, entrance, exit_pos);
+                    win_summary = optimal.map(|optimal| {
+                        format!(
+                            "Optimal: {}, You: {} ({:.0}% efficiency), explored {:.0}%",
+                            optimal,
+                            stats.steps,
+                            efficiency(optimal, stats.steps),
+                            explored_share(stats.explored.len(), tiles.vec.len())
+                        )
+                    });
+                    if let Some(summary) = &win_summary {
+                        println!("{}", summary);
+                    }
+                }
+                state = GameState::Won;
+                flood = Some(FloodReveal::new(&tiles, exit_pos));
+                particles.spawn_burst(players[winner].screen_pos, 24);
+            }
+        } else if state == GameState::Setup {
+            if is_mouse_button_pressed(MouseButton::Left) {
+                let mouse = camera
+                    .to_camera2d()
+                    .screen_to_world(mouse_position().into());
+                if let Some(tile) = tile_at_screen(&tiles, mouse.x, mouse.y) {
+                    setup.click(tile);
+                    setup_error = None;
+                }
+            }
+            if let Some(start) = setup.start {
+                overlay::tint_tiles(&tiles, &[start], Color::new(0.0, 1.0, 0.3, 0.6));
+            }
+            if let Some(exit) = setup.exit {
+                overlay::tint_tiles(&tiles, &[exit], Color::new(1.0, 0.9, 0.0, 0.6));
+            }
+
+            if is_key_pressed(KeyCode::Enter) {
+                match setup.validate(tiles.cols, tiles.rows) {
+                    Ok((start, exit)) => {
+                        // Un-braid first so the restore can't undo the new entrance
+                        if let Some(walls) = unbraided_walls.take() {
+                            restore_walls(&mut tiles, &walls);
+                        }
+                        clear_exits(&mut tiles);
+                        mark_exit(&mut tiles, exit);
+                        exit_pos = exit;
+                        mark_entrance(&mut tiles, start);
+                        entrance = start;
+                        players = spawn_players(&tiles, start, &theme, player_count, acceleration);
+                        time_limit = race_limit(&tiles, start, exit);
+                        teleports = place_teleports(&tiles, TELEPORT_PAIRS, &[start, exit]);
+                        traps = place_traps(
+                            &tiles,
+                            difficulty.traps(),
+                            start,
+                            exit,
+                            &teleport_pads(&teleports),
+                        );
+                        checkpoints = place_checkpoints(
+                            &solver::solve_astar(&tiles, start, exit).unwrap_or_default(),
+                            checkpoint_count,
+                            &teleport_pads(&teleports),
+                        );
+                        maze_info =
+                            MazeInfo::gather(&tiles, maze_info.algorithm, seed, start, exit);
+                        stats.next_maze();
+                        practice_path = None;
+                        solver_view = None;
+                        setup = Setup::new();
+                        state = GameState::Playing;
+                    }
+                    Err(err) => setup_error = Some(err),
+                }
+            }
+
+            set_default_camera();
+            let hint = match setup_error {
+                Some(err) => format!("Can't start: {}", err),
+                None if setup.picking_exit() => "Click the exit tile, Enter to play".to_string(),
+                None => "Click the start tile, Enter to play".to_string(),
+            };
+            let font_size = (screen_height() * 0.04).max(16.0);
+            draw_text(&hint, 20.0, screen_height() - font_size, font_size, WHITE);
+        } else if state == GameState::Lost {
+            for player in &players {
+                player.draw();
+            }
+
+            set_default_camera();
+            draw_rectangle(
+                0.0,
+                0.0,
+                screen_width(),
+                screen_height(),
+                Color::new(0.0, 0.0, 0.0, 0.6),
+            );
+            let font_size = (screen_height() * 0.08).max(24.0);
+            let title = "Time's up";
+            let title_size = measure_text(title, None, font_size as u16, 1.0);
+            draw_text(
+                title,
+                (screen_width() - title_size.width) / 2.0,
+                screen_height() / 2.0,
+                font_size,
+                WHITE,
+            );
+            let hint = "R / Enter: try again    N: new maze";
+            let hint_size = measure_text(hint, None, (font_size / 2.0) as u16, 1.0);
+            draw_text(
+                hint,
+                (screen_width() - hint_size.width) / 2.0,
+                screen_height() / 2.0 + font_size,
+                font_size / 2.0,
+                WHITE,
+            );
+
+            if is_key_pressed(KeyCode::R) || is_key_pressed(KeyCode::Enter) {
+                players = spawn_players(&tiles, entrance, &theme, player_count, acceleration);
+                stats.next_maze();
+                peek = Peek::new();
+                used_autopilot = false;
+                used_mercy = false;
+                state = GameState::Playing;
+            } else if is_key_pressed(KeyCode::N) {
+                regenerate = true;
+            }
+        } else if state == GameState::LevelSelect {
+            set_default_camera();
+            clear_background(theme.background);
+            let count = levels.len();
+            if is_key_pressed(KeyCode::Right) {
+                selected_level = (selected_level + 1) % count;
+            }
+            if is_key_pressed(KeyCode::Left) {
+                selected_level = (selected_level + count - 1) % count;
+            }
+
+            // Thumbnails share the width, keeping their own aspect ratio
+            let slot = screen_width() / count.max(3) as f32;
+            let font_size = (screen_height() * 0.04).max(16.0);
+            for (i, (name, _, thumbnail)) in levels.iter().enumerate() {
+                let scale = (slot * 0.8 / thumbnail.width())
+                    .min(screen_height() * 0.5 / thumbnail.height());
+                let size = vec2(thumbnail.width(), thumbnail.height()) * scale;
+                let x = slot * i as f32 + (slot - size.x) / 2.0;
+                let y = (screen_height() - size.y) / 2.0;
+                draw_texture_ex(
+                    thumbnail,
+                    x,
+                    y,
+                    WHITE,
+                    DrawTextureParams {
+                        dest_size: Some(size),
+                        ..Default::default()
+                    },
+                );
+                if i == selected_level {
+                    draw_rectangle_lines(x - 4.0, y - 4.0, size.x + 8.0, size.y + 8.0, 4.0, WHITE);
+                }
+                draw_text(name, x, y + size.y + font_size * 1.5, font_size, WHITE);
+            }
+            let hint = "Left / Right: pick a maze    Enter: play";
+            draw_text(hint, 20.0, screen_height() - font_size, font_size, WHITE);
+
+            if is_key_pressed(KeyCode::Enter) {
+                load_level = Some(levels[selected_level].1.clone());
+            }
+        } else if state == GameState::Won {
+            // Light up the maze from the exit, Space or Enter skips it
+            let finished = match flood.as_mut() {
+                Some(flood) => {
+                    let finished = flood.update(dt);
+                    flood.draw(&tiles);
+                    finished
+                }
+                None => true,
+            };
+            for player in &players {
+                player.draw();
+            }
+            particles.update(dt);
+            particles.draw();
+
+            if players.len() > 1 {
+                set_default_camera();
+                let font_size = (screen_height() * 0.08).max(24.0);
+                let banner = format!("Player {} wins!", winner + 1);
+                let banner_size = measure_text(&banner, None, font_size as u16, 1.0);
+                draw_text(
+                    &banner,
+                    (screen_width() - banner_size.width) / 2.0,
+                    screen_height() * 0.2,
+                    font_size,
+                    players[winner].color,
+                );
+            }
+
+            if winner == 0
+                && let Some(summary) = &win_summary
+            {
+                set_default_camera();
+                let font_size = (screen_height() * 0.04).max(16.0);
+                let size = measure_text(summary, None, font_size as u16, 1.0);
+                draw_text(
+                    summary,
+                    (screen_width() - size.width) / 2.0,
+                    screen_height() * 0.3,
+                    font_size,
+                    WHITE,
+                );
+            }
+
+            // R runs the same maze again against the ghost of this run
+            if is_key_pressed(KeyCode::R) {
+                players = spawn_players(&tiles, entrance, &theme, player_count, acceleration);
+                stats.next_maze();
+                peek = Peek::new();
+                flood = None;
+                particles = Particles::new();
+                used_autopilot = false;
+                used_mercy = false;
+                state = GameState::Playing;
+            } else if finished || is_key_pressed(KeyCode::Space) || is_key_pressed(KeyCode::Enter) {
+                regenerate = true;
+            }
+        }
+
+        set_default_camera();
+        if is_key_pressed(KeyCode::F) {
+            show_fps = !show_fps;
+        }
+        // Carving progress along the bottom edge
+        if state == GameState::Generating {
+            let fraction = carved_fraction(visited.len(), tiles.vec.len());
+            overlay::draw_progress_bar(fraction, WHITE);
+            let text = format!("Carved {} of {} tiles", visited.len(), tiles.vec.len());
+            let font_size = (screen_height() * 0.03).max(14.0);
+            draw_text(&text, 10.0, screen_height() - font_size, font_size, WHITE);
+        }
+        if is_key_pressed(KeyCode::I) {
+            show_info = !show_info;
+        }
+        if show_info && state != GameState::Generating {
+            draw_panel(&maze_info.lines(), true);
+        }
+        if let Some(steps) = steps_hint.take() {
+            let text = format!("{} steps to go", steps);
+            let font_size = (screen_height() * 0.04).max(16.0);
+            let width = measure_text(&text, None, font_size as u16, 1.0).width;
+            draw_text(
+                &text,
+                (screen_width() - width) / 2.0,
+                font_size,
+                font_size,
+                WHITE,
+            );
+        }
+        fps_counter.update(dt);
+        if show_fps {
+            let text = format!("FPS: {:.0}", fps_counter.fps);
+            let width = measure_text(&text, None, 20, 1.0).width;
+            draw_text(&text, screen_width() - width - 10.0, 20.0, 20.0, WHITE);
+        }
+
+        // Unplayable levels are reported and level select stays up
+        if let Some(mut level) = load_level.take() {
+            fit_tiles_to_screen(&mut level, screen_width(), screen_height());
+            let level_entrance = entrance_of(&level).unwrap_or((0, 0));
+            mark_entrance(&mut level, level_entrance);
+            let level_exit = match level.vec.iter().find(|tile| tile.exit) {
+                Some(exit) => (exit.col, exit.row),
+                None => choose_exit_tile(&mut level, level_entrance, settings.min_solution_len),
+            };
+            if let Err(err) = solver::validate_playable(&level) {
+                eprintln!("Can't play this level: {}", err);
+            } else {
+                tiles = level;
+                entrance = level_entrance;
+                exit_pos = level_exit;
+                first_tile_pos = tiles.vec.first().unwrap().screen_position;
+                players = spawn_players(&tiles, entrance, &theme, player_count, acceleration);
+                time_limit = race_limit(&tiles, entrance, exit_pos);
+                teleports.clear();
+                traps.clear();
+                checkpoints = place_checkpoints(
+                    &solver::solve_astar(&tiles, entrance, exit_pos).unwrap_or_default(),
+                    checkpoint_count,
+                    &[],
+                );
+                maze_info = MazeInfo::gather(&tiles, None, seed, entrance, exit_pos);
+                base_walls.clear();
+                unbraided_walls = None;
+                stats.next_maze();
+                practice_path = None;
+                solver_view = None;
+                autopilot = false;
+                used_autopilot = false;
+                used_mercy = false;
+                peek = Peek::new();
+                camera = MazeCamera::new(players[0].screen_pos);
+                state = GameState::Playing;
+            }
+        }
+
+        if regenerate {
+            regenerate = false;
+            state = GameState::Generating;
+            flood = None;
+            particles = Particles::new();
+            teleports.clear();
+            traps.clear();
+            checkpoints.clear();
+            // Daily mode replays today's maze, retries of a too easy one carry on
+            // from the current state so they stay the same for everyone
+            if daily && easy_regenerations == 0 {
+                rand::srand(seed);
+            }
+            tiles = new_tiles();
+            tiles.wrap = wrap;
+            unbraided_walls = None;
+            rooms = carve_rooms(&mut tiles, room_count, ROOM_MIN_SIZE, ROOM_MAX_SIZE);
+            carve_head = prepare_carving(&tiles, &rooms, &mut visited, &mut stack);
+            stats.next_maze();
+            practice_path = None;
+            solver_view = None;
+            autopilot = false;
+            used_autopilot = false;
+            used_mercy = false;
+            time_limit = None;
+            setup = Setup::new();
+            peek = Peek::new();
+            // A loaded maze may have been bigger than the generated ones
+            first_tile_pos = tiles.vec.first().unwrap().screen_position;
+            players = players
+                .iter()
+                .map(|player| {
+                    let col = player.tile_pos.0.min(tiles.cols - 1);
+                    let row = player.tile_pos.1.min(tiles.rows - 1);
+                    player_on_tile(&tiles, (col, row), player.color, acceleration)
+                })
+                .collect();
+        }
+
+        // F12 saves the frame as drawn to the working directory
+        if is_key_pressed(KeyCode::F12) {
+            let name = screenshot_name(macroquad::miniquad::date::now());
+            match save_screenshot(std::path::Path::new(&name)) {
+                Ok(()) => println!("Saved {}", name),
+                Err(err) => eprintln!("Can't save {}: {}", name, err),
+            }
+        }
+
+        // Browsers can't sleep the main thread, they pace frames themselves
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(cap) = fps_cap
+            && let Some(sleep) = frame_sleep(cap, macroquad::miniquad::date::now() - frame_start)
+        {
+            std::thread::sleep(sleep);
+        }
+
         next_frame().await
     }
 }
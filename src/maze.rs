@@ -1,30 +1,189 @@
-use crate::tile::{Tile, WALL_COLOR, Wall};
-use crate::utils::Vec2d;
+use crate::format::debug_grid;
+use crate::player::{Direction, MIN_WRAP_LEN, Teleport};
+use crate::settings::Settings;
+use crate::solver::{
+    compute_distances, farthest_tile, longest_path, manhattan, open_neighbors, solve_astar,
+};
+use crate::tile::{EXIT_COLOR, PATH_COLOR, Tile, WALL_COLOR, Wall};
+use crate::utils::{UnionFind, Vec2d};
 
 use macroquad::prelude::*;
+use macroquad::rand::{ChooseRandom, RandGenerator};
 use std::collections::HashSet;
 
 pub const NUMBER_OF_TILES_IN_BIGGER_AXIS: u16 = 30;
+// Smallest tile (in pixels) a fitted grid shrinks to, below it the maze keeps
+// this size and scrolls instead
+pub const MIN_TILE_PX: f32 = 8.0;
+// Default shortest allowed start-to-exit path (in steps), `--min-solution-len`
+// picks another
+pub const MIN_SOLUTION_LEN: usize = NUMBER_OF_TILES_IN_BIGGER_AXIS as usize;
+// Teleport pad pairs placed in each maze
+pub const TELEPORT_PAIRS: usize = 2;
+// Random exit picks before settling for the farthest tile
+const MAX_EXIT_ATTEMPTS: usize = 20;
+// Steps carved between clock checks while carving on a budget
+const BUDGET_CHUNK_STEPS: usize = 64;
+// Times a too easy maze is thrown away before it's accepted anyway
+pub const MAX_EASY_REGENERATIONS: usize = 3;
+// Side lengths (in tiles) of rooms carved with `--rooms`
+pub const ROOM_MIN_SIZE: usize = 2;
+pub const ROOM_MAX_SIZE: usize = 5;
+// Grid distance (ignoring walls) kept between a trap and the start or exit
+const TRAP_CLEARANCE: usize = 3;
+// Share of dead ends opened up when braiding with B
+pub const BRAID_SHARE: f32 = 0.5;
+// Side of the square generate_with_isolated_region walls off
+#[cfg(test)]
+const POCKET_SIZE: usize = 2;
 
-pub fn generate_tiles() -> Vec2d<Tile> {
-    let s_w = screen_width();
-    let s_h = screen_height();
-    let tile_size;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Algorithm {
+    // Animated iterative backtracking
+    #[default]
+    Backtracker,
+    // Randomized Kruskal, carved in one go
+    Kruskal,
+}
 
-    if s_w > s_h {
-        tile_size = (s_w / NUMBER_OF_TILES_IN_BIGGER_AXIS as f32) as u16;
-    } else {
-        tile_size = (s_h / NUMBER_OF_TILES_IN_BIGGER_AXIS as f32) as u16;
+impl Algorithm {
+    pub const ALL: [Algorithm; 2] = [Algorithm::Backtracker, Algorithm::Kruskal];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "backtracker" => Some(Algorithm::Backtracker),
+            "kruskal" => Some(Algorithm::Kruskal),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    // Minimum solution length, as a multiple of cols + rows
+    fn min_length_factor(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.25,
+            Difficulty::Normal => 0.5,
+            Difficulty::Hard => 1.0,
+        }
     }
 
-    let tiles_w: u16 = s_w as u16 / tile_size;
-    let tiles_h: u16 = s_h as u16 / tile_size;
+    // Trap tiles hidden off the solution
+    pub fn traps(self) -> usize {
+        match self {
+            Difficulty::Easy | Difficulty::Normal => 0,
+            Difficulty::Hard => 3,
+        }
+    }
 
-    let reminder_w: u16 = s_w as u16 % tile_size;
-    let reminder_h: u16 = s_h as u16 % tile_size;
+    // Minimum number of junctions along the solution
+    fn min_junctions(self) -> usize {
+        match self {
+            Difficulty::Easy => 0,
+            Difficulty::Normal => 2,
+            Difficulty::Hard => 4,
+        }
+    }
+}
 
-    let first_x = reminder_w / 2;
-    let first_y = reminder_h / 2;
+pub fn generate_tiles(fixed_tile_px: Option<f32>) -> Vec2d<Tile> {
+    layout_tiles(screen_width(), screen_height(), fixed_tile_px)
+}
+
+pub fn generate_tiles_letterboxed() -> Vec2d<Tile> {
+    layout_tiles_letterboxed(screen_width(), screen_height())
+}
+
+// NUMBER_OF_TILES_IN_BIGGER_AXIS whole square tiles along the bigger axis and
+// as many as fit along the other, centered so opposite margins match (the
+// background shows through them)
+pub fn layout_tiles_letterboxed(s_w: f32, s_h: f32) -> Vec2d<Tile> {
+    let bigger = s_w.max(s_h);
+    let axis = |s: f32| ((NUMBER_OF_TILES_IN_BIGGER_AXIS as f32 * s / bigger) as usize).max(1);
+    let mut tiles = blank_tiles(axis(s_w), axis(s_h));
+    let tile_size = (s_w / tiles.cols as f32)
+        .min(s_h / tiles.rows as f32)
+        .floor()
+        .max(1.0);
+    for tile in &mut tiles.vec {
+        tile.screen_position = Vec2::new(tile.col as f32, tile.row as f32) * tile_size;
+        tile.width = tile_size;
+        tile.height = tile_size;
+    }
+    center_tiles(&mut tiles, s_w, s_h);
+    tiles
+}
+
+// Where a `grid` px long span starts to sit in the middle of `screen` px, on a
+// whole pixel so tile edges stay crisp. Rounding the real (possibly
+// fractional) half margin keeps the two margins within 1px of each other.
+// A grid longer than the screen starts at 0 and scrolls
+pub fn centered_start(screen: f32, grid: f32) -> f32 {
+    ((screen - grid) / 2.0).max(0.0).round()
+}
+
+// Shift the grid so it sits in the middle of an s_w x s_h screen, on whole pixels
+pub fn center_tiles(tiles: &mut Vec2d<Tile>, s_w: f32, s_h: f32) {
+    let bounds = maze_bounds(tiles);
+    let offset = Vec2::new(
+        centered_start(s_w, bounds.w) - bounds.x,
+        centered_start(s_h, bounds.h) - bounds.y,
+    );
+    for tile in &mut tiles.vec {
+        tile.screen_position += offset;
+    }
+}
+
+// Fits the grid into the screen, or with `fixed_tile_px` keeps that tile size and
+// lets the grid grow past the screen, at least covering it and having
+// NUMBER_OF_TILES_IN_BIGGER_AXIS tiles along the bigger axis. Fitting never goes
+// below MIN_TILE_PX, the grid scrolls like a fixed size one then
+pub fn layout_tiles(s_w: f32, s_h: f32, fixed_tile_px: Option<f32>) -> Vec2d<Tile> {
+    let bigger = s_w.max(s_h);
+    if fixed_tile_px.is_none() && bigger / (NUMBER_OF_TILES_IN_BIGGER_AXIS as f32) < MIN_TILE_PX {
+        println!(
+            "Tiles would be under {}px, scrolling the maze instead",
+            MIN_TILE_PX
+        );
+        return layout_tiles(s_w, s_h, Some(MIN_TILE_PX));
+    }
+    let (tile_size, tiles_w, tiles_h) = match fixed_tile_px {
+        Some(px) => {
+            let tile_size = px.max(1.0) as u16;
+            let size = tile_size as f32;
+            let axis = |s: f32| {
+                let configured = NUMBER_OF_TILES_IN_BIGGER_AXIS as f32 * s / bigger;
+                configured.ceil().max((s / size).ceil()) as u16
+            };
+            (tile_size, axis(s_w), axis(s_h))
+        }
+        None => {
+            let tile_size = (bigger / NUMBER_OF_TILES_IN_BIGGER_AXIS as f32) as u16;
+            (tile_size, s_w as u16 / tile_size, s_h as u16 / tile_size)
+        }
+    };
+
+    // Center the grid when it fits, otherwise it starts at the origin and scrolls
+    let size = tile_size as f32;
+    let first_x = centered_start(s_w, tiles_w as f32 * size);
+    let first_y = centered_start(s_h, tiles_h as f32 * size);
     let mut tiles: Vec<Tile> = Vec::new();
 
     for y in 0..tiles_h {
@@ -32,10 +191,10 @@ pub fn generate_tiles() -> Vec2d<Tile> {
             tiles.push(Tile::new(
                 x as usize,
                 y as usize,
-                (x * tile_size + first_x) as f32,
-                (y * tile_size + first_y) as f32,
-                tile_size as f32,
-                tile_size as f32,
+                x as f32 * size + first_x,
+                y as f32 * size + first_y,
+                size,
+                size,
                 WALL_COLOR,
             ));
         }
@@ -44,12 +203,54 @@ pub fn generate_tiles() -> Vec2d<Tile> {
     Vec2d::new(tiles, tiles_h as usize, tiles_w as usize)
 }
 
+// Scale and center an existing grid (e.g. a loaded one) to fit the screen
+pub fn fit_tiles_to_screen(tiles: &mut Vec2d<Tile>, s_w: f32, s_h: f32) {
+    let tile_size = (s_w / tiles.cols as f32)
+        .min(s_h / tiles.rows as f32)
+        .floor();
+    let first_x = centered_start(s_w, tile_size * tiles.cols as f32);
+    let first_y = centered_start(s_h, tile_size * tiles.rows as f32);
+
+    for tile in &mut tiles.vec {
+        tile.screen_position = Vec2::new(
+            first_x + tile.col as f32 * tile_size,
+            first_y + tile.row as f32 * tile_size,
+        );
+        tile.width = tile_size;
+        tile.height = tile_size;
+    }
+}
+
+// Screen space rect covering the whole grid, from the first to the last tile
+pub fn maze_bounds(tiles: &Vec2d<Tile>) -> Rect {
+    let (Some(first), Some(last)) = (tiles.vec.first(), tiles.vec.last()) else {
+        return Rect::default();
+    };
+    let start = first.screen_position;
+    let end = last.screen_position + vec2(last.width, last.height);
+    Rect::new(start.x, start.y, end.x - start.x, end.y - start.y)
+}
+
 pub fn iterative_backtracking(
     tiles: &mut Vec2d<Tile>,
     visited: &mut HashSet<(usize, usize)>,
     stack: &mut Vec<(usize, usize)>,
     start_position: (usize, usize),
     max_steps: usize,
+) -> (usize, usize) {
+    backtrack(tiles, visited, stack, start_position, max_steps, |len| {
+        rand::gen_range(0, len)
+    })
+}
+
+// The backtracker with `pick(len)` choosing which of `len` neighbors to carve to
+fn backtrack(
+    tiles: &mut Vec2d<Tile>,
+    visited: &mut HashSet<(usize, usize)>,
+    stack: &mut Vec<(usize, usize)>,
+    start_position: (usize, usize),
+    max_steps: usize,
+    pick: impl Fn(usize) -> usize,
 ) -> (usize, usize) {
     let (mut curr_col, mut curr_row) = start_position;
     let mut steps_taken = 0;
@@ -66,13 +267,12 @@ pub fn iterative_backtracking(
     while visited.len() != len && (unlimited || steps_taken < max_steps) {
         steps_taken += 1;
 
-        let neighbors =
-            get_unvisited_neighbors(curr_col, curr_row, tiles.cols, tiles.rows, &visited);
+        let neighbors = get_unvisited_neighbors(tiles, (curr_col, curr_row), visited);
         // println!("curr (col, row): {:?}", (curr_col, curr_row));
         // println!("neighbors: {:?}", neighbors);
 
         if !neighbors.is_empty() {
-            let random_index = rand::gen_range(0, neighbors.len());
+            let random_index = pick(neighbors.len());
             let (nc, nr) = neighbors[random_index];
             remove_walls_between_positions(tiles, (curr_col, curr_row), (nc, nr));
 
@@ -94,6 +294,107 @@ pub fn iterative_backtracking(
     (curr_col, curr_row)
 }
 
+// Braid the start and open extra walls on a freshly carved perfect maze
+pub fn add_loops(
+    tiles: &mut Vec2d<Tile>,
+    start: (usize, usize),
+    target_loops: Option<usize>,
+    settings: &Settings,
+) {
+    // Even the start flow opening would add a loop. Rooms hold loops of their
+    // own, so only a maze without them is really perfect
+    if settings.perfect {
+        match count_loops(tiles) {
+            0 => println!("Perfect maze, exactly one solution"),
+            loops => println!("No loops added, rooms keep {}", loops),
+        }
+        return;
+    }
+    ensure_start_flow(tiles, start);
+    if let Some(target) = target_loops {
+        remove_walls_to_loop_count(tiles, target);
+    } else {
+        let (min_share, max_share) = settings.loop_share;
+        let precentage = rand::gen_range(min_share, max_share);
+        remove_random_walls(tiles, precentage);
+    }
+    assert_wall_consistency(tiles);
+    println!("Braided maze, loops: {}", count_loops(tiles));
+}
+
+// Turn a fully carved maze into a playable one: join the rooms, pick the
+// entrance and exit and add the loops. The maze runs between the two ends of
+// the perfect maze's longest path unless border_exit moves the exit to the
+// border or random_spawn puts the entrance on a random tile far from the exit.
+// Returns the walls before the loops went in, the entrance and the exit
+pub fn finish_generation(
+    tiles: &mut Vec2d<Tile>,
+    has_rooms: bool,
+    target_loops: Option<usize>,
+    settings: &Settings,
+) -> (Vec<u8>, (usize, usize), (usize, usize)) {
+    // Every room carved its own tree, join them up
+    if has_rooms {
+        connect_regions(tiles);
+    }
+    assert_wall_consistency(tiles);
+    let base_walls = snapshot_walls(tiles);
+    // Only the perfect maze has a single longest path, find it before the loops
+    let longest = longest_path(tiles);
+    let mut entrance = longest.first().copied().unwrap_or((0, 0));
+    add_loops(tiles, entrance, target_loops, settings);
+    mark_entrance(tiles, entrance);
+    let border_exit = settings
+        .border_exit
+        .then(|| choose_border_exit(tiles, entrance))
+        .flatten();
+    // The loops can cut the longest path short of min_solution_len
+    let long_enough = |end: (usize, usize)| {
+        compute_distances(tiles, entrance)
+            .index(end.0, end.1)
+            .is_some_and(|distance| distance > 0 && distance >= settings.min_solution_len)
+    };
+    let exit = match (border_exit, longest.last()) {
+        (Some(exit), _) => exit,
+        (None, Some(&end)) if long_enough(end) => {
+            mark_exit(tiles, end);
+            end
+        }
+        _ => choose_exit_tile(tiles, entrance, settings.min_solution_len),
+    };
+    // The exit stays where it was picked, the players move somewhere far from it
+    if settings.random_spawn {
+        entrance = random_spawn(tiles, exit);
+        mark_entrance(tiles, entrance);
+    }
+    (base_walls, entrance, exit)
+}
+
+// Carve until the maze is done or `budget_ms` has passed on `clock` (in
+// seconds), the rest is picked up from the returned head next frame
+pub fn generate_with_budget(
+    tiles: &mut Vec2d<Tile>,
+    visited: &mut HashSet<(usize, usize)>,
+    stack: &mut Vec<(usize, usize)>,
+    mut head: (usize, usize),
+    budget_ms: f64,
+    clock: impl Fn() -> f64,
+) -> (usize, usize) {
+    let started = clock();
+    while visited.len() != tiles.vec.len() && (clock() - started) * 1000.0 < budget_ms {
+        head = iterative_backtracking(tiles, visited, stack, head, BUDGET_CHUNK_STEPS);
+    }
+    head
+}
+
+// Share of the maze the generator has reached, an empty maze counts as done
+pub fn carved_fraction(visited_len: usize, total: usize) -> f32 {
+    if total == 0 {
+        return 1.0;
+    }
+    (visited_len as f32 / total as f32).min(1.0)
+}
+
 pub fn remove_random_walls(tiles: &mut Vec2d<Tile>, percentage: f32) {
     // Ensure percentage is within valid range (0.0 to 1.0)
     let percentage = percentage.clamp(0.0, 1.0);
@@ -109,150 +410,1672 @@ pub fn remove_random_walls(tiles: &mut Vec2d<Tile>, percentage: f32) {
         total_internal_walls, walls_to_remove
     );
 
-    // Track which walls we've already removed
-    let mut removed_connections: HashSet<((usize, usize), (usize, usize))> = HashSet::new();
+    let count = remove_n_random_walls(tiles, walls_to_remove);
+    println!("Removed {} walls", count);
+}
+
+// Remove exactly `n` still standing internal walls (fewer if not that many
+// are left), returns how many were removed. Takes a single draw from the
+// global RNG whatever the maze, so later draws stay in step for replays
+pub fn remove_n_random_walls(tiles: &mut Vec2d<Tile>, n: usize) -> usize {
+    let rng = RandGenerator::new();
+    rng.srand(rand::rand() as u64);
+    remove_n_walls_with(tiles, n, &rng)
+}
+
+// Fisher-Yates shuffle the closed internal walls (in internal_walls order)
+// with `rng`, then remove the first `n`
+pub fn remove_n_walls_with(tiles: &mut Vec2d<Tile>, n: usize, rng: &RandGenerator) -> usize {
+    let mut candidates: Vec<((usize, usize), (usize, usize))> = internal_walls(tiles)
+        .into_iter()
+        .filter(|&(a, b)| !are_connected(tiles, a, b))
+        .collect();
+    candidates.shuffle_with_state(rng);
+    candidates.truncate(n);
+    for &(a, b) in &candidates {
+        remove_walls_between_positions(tiles, a, b);
+    }
+    candidates.len()
+}
+
+// Open walls between tiles that are already connected, each one adds exactly
+// one loop, until the maze has `target_loops` (or no walls are left to try).
+// Returns the final loop count
+pub fn remove_walls_to_loop_count(tiles: &mut Vec2d<Tile>, target_loops: usize) -> usize {
+    let cols = tiles.cols;
+    let mut sets = UnionFind::new(tiles.vec.len());
+    let mut closed = Vec::new();
+    for (a, b) in internal_walls(tiles) {
+        if are_connected(tiles, a, b) {
+            sets.union(a.1 * cols + a.0, b.1 * cols + b.0);
+        } else {
+            closed.push((a, b));
+        }
+    }
+    closed.shuffle();
+
+    let mut loops = count_loops(tiles);
+    for (a, b) in closed {
+        if loops >= target_loops {
+            break;
+        }
+        // Joining two separate regions would merge them without a loop
+        if sets.find(a.1 * cols + a.0) == sets.find(b.1 * cols + b.0) {
+            remove_walls_between_positions(tiles, a, b);
+            loops += 1;
+        }
+    }
+    loops
+}
+
+// Wall mask of every tile (Left=1, Top=2, Right=4, Bottom=8) in grid order
+pub fn snapshot_walls(tiles: &Vec2d<Tile>) -> Vec<u8> {
+    tiles.vec.iter().map(Tile::wall_mask).collect()
+}
+
+// Put back the walls saved by snapshot_walls, tile colors are left alone
+pub fn restore_walls(tiles: &mut Vec2d<Tile>, snapshot: &[u8]) {
+    assert_eq!(
+        snapshot.len(),
+        tiles.vec.len(),
+        "snapshot is for another maze"
+    );
+    for (i, &mask) in snapshot.iter().enumerate() {
+        tiles.set_walls_from_mask((i % tiles.cols, i / tiles.cols), mask);
+    }
+}
+
+pub fn remove_walls_between_positions(
+    tiles: &mut Vec2d<Tile>,
+    pos1: (usize, usize),
+    pos2: (usize, usize),
+) {
+    let Some((wall1, wall2)) = shared_walls(tiles, pos1, pos2) else {
+        return;
+    };
+    tiles.remove_wall(pos1, wall1);
+    tiles.remove_wall(pos2, wall2);
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Right,
+    Direction::Down,
+    Direction::Left,
+];
 
-    // Try to remove the specified number of walls
-    let mut count = 0;
-    let mut attempts = 0;
-    let max_attempts = total_internal_walls * 5; // Limit attempts to avoid infinite loop
+// The wall on a's side and on b's side between two neighbors, None if they
+// aren't adjacent (across the edge counts in a wrapping maze)
+fn shared_walls(tiles: &Vec2d<Tile>, a: (usize, usize), b: (usize, usize)) -> Option<(Wall, Wall)> {
+    let direction = DIRECTIONS
+        .into_iter()
+        .find(|direction| direction.neighbor(a, tiles.cols, tiles.rows, tiles.wrap) == Some(b))?;
+    Some((direction.wall()?, direction.opposite().wall()?))
+}
 
-    while count < walls_to_remove && attempts < max_attempts {
-        attempts += 1;
+fn get_unvisited_neighbors(
+    tiles: &Vec2d<Tile>,
+    pos: (usize, usize),
+    visited: &HashSet<(usize, usize)>,
+) -> Vec<(usize, usize)> {
+    DIRECTIONS
+        .into_iter()
+        .filter_map(|direction| direction.neighbor(pos, tiles.cols, tiles.rows, tiles.wrap))
+        .filter(|next| !visited.contains(next))
+        .collect()
+}
+
+// Picks a random exit at least `min_solution_len` steps away from `start`.
+// When no such tile is found within MAX_EXIT_ATTEMPTS picks (or none exists),
+// the tile farthest from `start` is used instead.
+pub fn choose_exit_tile(
+    tiles: &mut Vec2d<Tile>,
+    start: (usize, usize),
+    min_solution_len: usize,
+) -> (usize, usize) {
+    let distances = compute_distances(tiles, start);
+    let mut exit = None;
 
-        // Pick a random tile
+    for _ in 0..MAX_EXIT_ATTEMPTS {
         let col = rand::gen_range(0, tiles.cols);
         let row = rand::gen_range(0, tiles.rows);
+        if distances
+            .index(col, row)
+            .is_some_and(|d| d >= min_solution_len)
+        {
+            exit = Some((col, row));
+            break;
+        }
+    }
 
-        // Pick a random direction (0=right, 1=bottom, 2=left, 3=top)
-        let direction = rand::gen_range(0, 4);
+    let (col, row) = exit.unwrap_or_else(|| {
+        println!(
+            "No exit found at distance {}, using farthest tile",
+            min_solution_len
+        );
+        farthest_tile(&distances)
+    });
+    mark_exit(tiles, (col, row));
+    (col, row)
+}
 
-        // Find neighboring tile in chosen direction
-        let neighbor = match direction {
-            0 => {
-                if col < tiles.cols - 1 {
-                    Some((col + 1, row))
-                } else {
-                    None
-                }
-            }
-            1 => {
-                if row < tiles.rows - 1 {
-                    Some((col, row + 1))
-                } else {
-                    None
-                }
+// The reachable border tile farthest from `start` as the exit, opened to the
+// outside like the entrance. None when no border tile other than `start` can
+// be reached, or the maze wraps and has no border
+pub fn choose_border_exit(
+    tiles: &mut Vec2d<Tile>,
+    start: (usize, usize),
+) -> Option<(usize, usize)> {
+    let distances = compute_distances(tiles, start);
+    let (_, exit) = (0..tiles.vec.len())
+        .map(|i| tiles.coords(i))
+        .filter(|&pos| pos != start && !border_walls(tiles, pos).is_empty())
+        .filter_map(|pos| {
+            distances
+                .index(pos.0, pos.1)
+                .map(|distance| (distance, pos))
+        })
+        .max_by_key(|&(distance, _)| distance)?;
+    mark_exit(tiles, exit);
+    open_border(tiles, exit);
+    Some(exit)
+}
+
+// Random tile reachable from `exit`, at least half as far from it as the
+// farthest one so the walk stays long. Only a maze of one tile gives `exit`
+pub fn random_spawn(tiles: &Vec2d<Tile>, exit: (usize, usize)) -> (usize, usize) {
+    let distances = compute_distances(tiles, exit);
+    let farthest = distances.vec.iter().flatten().copied().max().unwrap_or(0);
+    let far: Vec<(usize, usize)> = (0..tiles.vec.len())
+        .filter(|&i| distances.vec[i].is_some_and(|d| d > 0 && d * 2 >= farthest))
+        .map(|i| tiles.coords(i))
+        .collect();
+    far.choose().copied().unwrap_or(exit)
+}
+
+pub fn mark_exit(tiles: &mut Vec2d<Tile>, pos: (usize, usize)) {
+    let tile = tiles.index_mut(pos.0, pos.1);
+    tile.exit = true;
+    tile.color = EXIT_COLOR;
+}
+
+// Unmark the exits and close any border opening they had
+pub fn clear_exits(tiles: &mut Vec2d<Tile>) {
+    let marked: Vec<(usize, usize)> = tiles
+        .vec
+        .iter()
+        .filter(|tile| tile.exit)
+        .map(|tile| (tile.col, tile.row))
+        .collect();
+    for pos in marked {
+        let border = border_walls(tiles, pos);
+        let tile = tiles.index_mut(pos.0, pos.1);
+        tile.exit = false;
+        tile.color = PATH_COLOR;
+        for wall in border {
+            tiles.set_wall(pos, wall, true);
+        }
+    }
+}
+
+// Outer walls of the tile at `pos`, none for inner tiles or wrapped mazes
+pub fn border_walls(tiles: &Vec2d<Tile>, pos: (usize, usize)) -> Vec<Wall> {
+    if tiles.wrap {
+        return Vec::new();
+    }
+    let mut walls = Vec::new();
+    if pos.0 == 0 {
+        walls.push(Wall::Left);
+    }
+    if pos.1 == 0 {
+        walls.push(Wall::Top);
+    }
+    if pos.0 + 1 == tiles.cols {
+        walls.push(Wall::Right);
+    }
+    if pos.1 + 1 == tiles.rows {
+        walls.push(Wall::Bottom);
+    }
+    walls
+}
+
+// The only entrance, opened to the outside when it sits on the border
+pub fn mark_entrance(tiles: &mut Vec2d<Tile>, pos: (usize, usize)) {
+    clear_entrances(tiles);
+    tiles.index_mut(pos.0, pos.1).entrance = true;
+    open_border(tiles, pos);
+}
+
+// Open one outer wall of a border tile, unless one already is
+pub fn open_border(tiles: &mut Vec2d<Tile>, pos: (usize, usize)) {
+    let border = border_walls(tiles, pos);
+    if border
+        .iter()
+        .all(|wall| tiles.index(pos.0, pos.1).walls().contains(wall))
+        && let Some(&wall) = border.first()
+    {
+        tiles.set_wall(pos, wall, false);
+    }
+}
+
+// Unmark the entrance and close its border opening again
+pub fn clear_entrances(tiles: &mut Vec2d<Tile>) {
+    let marked: Vec<(usize, usize)> = tiles
+        .vec
+        .iter()
+        .filter(|tile| tile.entrance)
+        .map(|tile| (tile.col, tile.row))
+        .collect();
+    for pos in marked {
+        let border = border_walls(tiles, pos);
+        tiles.index_mut(pos.0, pos.1).entrance = false;
+        for wall in border {
+            tiles.set_wall(pos, wall, true);
+        }
+    }
+}
+
+pub fn entrance_of(tiles: &Vec2d<Tile>) -> Option<(usize, usize)> {
+    tiles
+        .vec
+        .iter()
+        .find(|tile| tile.entrance)
+        .map(|tile| (tile.col, tile.row))
+}
+
+// Grid of fully walled tiles one pixel apart, for work that never draws
+pub fn blank_tiles(cols: usize, rows: usize) -> Vec2d<Tile> {
+    let mut tiles = Vec::with_capacity(cols * rows);
+    for row in 0..rows {
+        for col in 0..cols {
+            tiles.push(Tile::new(
+                col, row, col as f32, row as f32, 1.0, 1.0, WALL_COLOR,
+            ));
+        }
+    }
+    Vec2d::new(tiles, rows, cols)
+}
+
+// Perfect maze with corridors `width` tiles wide: a logical_cols x logical_rows
+// maze scaled up so every cell is an open width x width block and every opening
+// between two cells spans the whole shared side
+pub fn generate_thick(logical_cols: usize, logical_rows: usize, width: usize) -> Vec2d<Tile> {
+    let width = width.max(1);
+    let mut logical = blank_tiles(logical_cols, logical_rows);
+    carve(&mut logical, Algorithm::default());
+
+    let mut tiles = blank_tiles(logical_cols * width, logical_rows * width);
+    let cell = |(col, row): (usize, usize)| (col / width, row / width);
+    for i in 0..tiles.vec.len() {
+        let pos = tiles.coords(i);
+        for next in [(pos.0 + 1, pos.1), (pos.0, pos.1 + 1)] {
+            if next.0 < tiles.cols
+                && next.1 < tiles.rows
+                && (cell(pos) == cell(next) || are_connected(&logical, cell(pos), cell(next)))
+            {
+                remove_walls_between_positions(&mut tiles, pos, next);
             }
-            2 => {
-                if col > 0 {
-                    Some((col - 1, row))
-                } else {
-                    None
-                }
+        }
+    }
+    tiles
+}
+
+// The maze turned a quarter clockwise, tile (col, row) moves to
+// (rows - 1 - row, col) and every wall to the next side round
+pub fn rotate_90(tiles: &Vec2d<Tile>) -> Vec2d<Tile> {
+    let rotate = |wall: Wall| match wall {
+        Wall::Top => Wall::Right,
+        Wall::Right => Wall::Bottom,
+        Wall::Bottom => Wall::Left,
+        Wall::Left => Wall::Top,
+    };
+    let rows = tiles.rows;
+    transformed(
+        tiles,
+        tiles.rows,
+        tiles.cols,
+        |(col, row)| (rows - 1 - row, col),
+        rotate,
+    )
+}
+
+// The maze flipped left to right, left and right walls swap
+pub fn mirror_horizontal(tiles: &Vec2d<Tile>) -> Vec2d<Tile> {
+    let mirror = |wall: Wall| match wall {
+        Wall::Left => Wall::Right,
+        Wall::Right => Wall::Left,
+        other => other,
+    };
+    let cols = tiles.cols;
+    transformed(
+        tiles,
+        tiles.cols,
+        tiles.rows,
+        |(col, row)| (cols - 1 - col, row),
+        mirror,
+    )
+}
+
+// `left` and `right` side by side as one maze, joined through a single opened
+// wall on a random row of the seam. Tiles keep their walls, exits and
+// entrances and are laid out on left's tile size. Both need the same rows
+pub fn concat_horizontal(left: &Vec2d<Tile>, right: &Vec2d<Tile>) -> Vec2d<Tile> {
+    assert_eq!(left.rows, right.rows, "mazes of different heights");
+    let (cols, rows) = (left.cols + right.cols, left.rows);
+    let origin = left.vec[0].screen_position;
+    let (width, height) = (left.vec[0].width, left.vec[0].height);
+    let mut vec = Vec::with_capacity(cols * rows);
+    for row in 0..rows {
+        let left_row = left.row(row).iter().map(|tile| (tile, 0));
+        let right_row = right.row(row).iter().map(|tile| (tile, left.cols));
+        for (tile, offset) in left_row.chain(right_row) {
+            let col = tile.col + offset;
+            let mut moved = tile.clone();
+            moved.col = col;
+            moved.screen_position = origin + vec2(col as f32 * width, row as f32 * height);
+            moved.width = width;
+            moved.height = height;
+            vec.push(moved);
+        }
+    }
+    let mut out = Vec2d::new(vec, rows, cols);
+    // Border openings on the seam would leave one sided walls, close it first
+    for row in 0..rows {
+        out.set_wall((left.cols - 1, row), Wall::Right, true);
+        out.set_wall((left.cols, row), Wall::Left, true);
+    }
+    let row = rand::gen_range(0, rows);
+    remove_walls_between_positions(&mut out, (left.cols - 1, row), (left.cols, row));
+    out
+}
+
+// New cols x rows grid laid out from the old one's first tile, each tile moved
+// to `move_to` of its position with its walls mapped by `map_wall`
+fn transformed(
+    tiles: &Vec2d<Tile>,
+    cols: usize,
+    rows: usize,
+    move_to: impl Fn((usize, usize)) -> (usize, usize),
+    map_wall: impl Fn(Wall) -> Wall,
+) -> Vec2d<Tile> {
+    let origin = tiles.vec[0].screen_position;
+    let (width, height) = (tiles.vec[0].width, tiles.vec[0].height);
+    let mut vec: Vec<Option<Tile>> = vec![None; tiles.vec.len()];
+    let mut masks = vec![0; tiles.vec.len()];
+    for tile in &tiles.vec {
+        let (col, row) = move_to((tile.col, tile.row));
+        let mut moved = Tile::new(
+            col,
+            row,
+            origin.x + col as f32 * width,
+            origin.y + row as f32 * height,
+            width,
+            height,
+            tile.color,
+        );
+        masks[row * cols + col] = tile.walls().iter().map(|&wall| map_wall(wall) as u8).sum();
+        moved.exit = tile.exit;
+        moved.entrance = tile.entrance;
+        vec[row * cols + col] = Some(moved);
+    }
+    let mut out = Vec2d::new(vec.into_iter().map(Option::unwrap).collect(), rows, cols);
+    out.wrap = tiles.wrap;
+    restore_walls(&mut out, &masks);
+    out
+}
+
+// Carve a whole perfect maze in one go with `algorithm`
+pub fn carve(tiles: &mut Vec2d<Tile>, algorithm: Algorithm) {
+    match algorithm {
+        Algorithm::Backtracker => {
+            let mut visited = HashSet::new();
+            let mut stack = Vec::new();
+            let head = prepare_carving(tiles, &[], &mut visited, &mut stack);
+            iterative_backtracking(tiles, &mut visited, &mut stack, head, 0);
+        }
+        Algorithm::Kruskal => kruskal(tiles),
+    }
+}
+
+// carve drawing from `rng` instead of the global RNG
+pub fn carve_with(tiles: &mut Vec2d<Tile>, algorithm: Algorithm, rng: &RandGenerator) {
+    match algorithm {
+        Algorithm::Backtracker => {
+            let head = (rng.gen_range(0, tiles.cols), rng.gen_range(0, tiles.rows));
+            backtrack(
+                tiles,
+                &mut HashSet::new(),
+                &mut Vec::new(),
+                head,
+                0,
+                |len| rng.gen_range(0, len),
+            );
+        }
+        Algorithm::Kruskal => {
+            let mut walls = internal_walls(tiles);
+            walls.shuffle_with_state(rng);
+            join_regions(tiles, walls);
+        }
+    }
+}
+
+// Grid position under a point in maze (world) coordinates, None outside the
+// grid. Tiles own their top and left edges
+pub fn tile_at_screen(tiles: &Vec2d<Tile>, x: f32, y: f32) -> Option<(usize, usize)> {
+    let first = tiles.vec.first()?;
+    let col = ((x - first.screen_position.x) / first.width).floor();
+    let row = ((y - first.screen_position.y) / first.height).floor();
+    if col < 0.0 || row < 0.0 {
+        return None;
+    }
+    let (col, row) = (col as usize, row as usize);
+    (col < tiles.cols && row < tiles.rows).then_some((col, row))
+}
+
+// Tiles closed on three sides
+pub fn dead_ends(tiles: &Vec2d<Tile>) -> Vec<(usize, usize)> {
+    tiles
+        .vec
+        .iter()
+        .filter(|tile| tile.walls().len() == 3)
+        .map(|tile| (tile.col, tile.row))
+        .collect()
+}
+
+// Open one more wall on the start tile and its open neighbors when they're
+// dead ends, so the first move never runs straight into a wall
+pub fn ensure_start_flow(tiles: &mut Vec2d<Tile>, start: (usize, usize)) {
+    let mut around = vec![start];
+    around.extend(open_neighbors(tiles, start));
+    for pos in around {
+        if tiles.index(pos.0, pos.1).walls().len() == 3 {
+            open_dead_end(tiles, pos);
+        }
+    }
+}
+
+// Open a random `share` of the dead ends into loops, returns the walls opened
+pub fn braid(tiles: &mut Vec2d<Tile>, share: f32) -> usize {
+    let mut ends = dead_ends(tiles);
+    ends.shuffle();
+    let count = (ends.len() as f32 * share.clamp(0.0, 1.0)).round() as usize;
+    let mut opened = 0;
+    for pos in ends.into_iter().take(count) {
+        // Opening a neighboring dead end may already have fixed this one
+        if tiles.index(pos.0, pos.1).walls().len() == 3 && open_dead_end(tiles, pos) {
+            opened += 1;
+        }
+    }
+    opened
+}
+
+// Knock down one closed inner wall of `pos`, into another dead end when there
+// is one so a single opening fixes both
+fn open_dead_end(tiles: &mut Vec2d<Tile>, pos: (usize, usize)) -> bool {
+    let closed: Vec<(usize, usize)> = DIRECTIONS
+        .into_iter()
+        .filter_map(|direction| direction.neighbor(pos, tiles.cols, tiles.rows, tiles.wrap))
+        .filter(|&next| !are_connected(tiles, pos, next))
+        .collect();
+    let ends: Vec<(usize, usize)> = closed
+        .iter()
+        .copied()
+        .filter(|&(col, row)| tiles.index(col, row).walls().len() == 3)
+        .collect();
+    match ends.choose().or(closed.choose()) {
+        Some(&next) => {
+            remove_walls_between_positions(tiles, pos, next);
+            true
+        }
+        None => false,
+    }
+}
+
+// Link random pairs of tiles with teleport pads, never on an avoided tile
+pub fn place_teleports(
+    tiles: &Vec2d<Tile>,
+    pairs: usize,
+    avoid: &[(usize, usize)],
+) -> Vec<Teleport> {
+    let mut taken: HashSet<(usize, usize)> = avoid.iter().copied().collect();
+    let free = tiles.vec.len().saturating_sub(taken.len());
+    let pairs = pairs.min(free / 2);
+    let pick = |taken: &mut HashSet<(usize, usize)>| loop {
+        let pos = (
+            rand::gen_range(0, tiles.cols),
+            rand::gen_range(0, tiles.rows),
+        );
+        if taken.insert(pos) {
+            return pos;
+        }
+    };
+
+    (0..pairs)
+        .map(|_| (pick(&mut taken), pick(&mut taken)))
+        .collect()
+}
+
+// Trap tiles off the start-to-exit solution (so the maze stays winnable), at
+// least TRAP_CLEARANCE steps from both ends and never on an avoided tile
+pub fn place_traps(
+    tiles: &Vec2d<Tile>,
+    count: usize,
+    start: (usize, usize),
+    exit: (usize, usize),
+    avoid: &[(usize, usize)],
+) -> HashSet<(usize, usize)> {
+    let mut blocked: HashSet<(usize, usize)> = avoid.iter().copied().collect();
+    blocked.extend(solve_astar(tiles, start, exit).unwrap_or_default());
+    let mut free: Vec<(usize, usize)> = (0..tiles.vec.len())
+        .map(|i| tiles.coords(i))
+        .filter(|pos| !blocked.contains(pos))
+        .filter(|&pos| {
+            manhattan(tiles, pos, start) >= TRAP_CLEARANCE
+                && manhattan(tiles, pos, exit) >= TRAP_CLEARANCE
+        })
+        .collect();
+    free.shuffle();
+    free.into_iter().take(count).collect()
+}
+
+// `count` tiles spread evenly along a start-to-exit `solution`, in the order
+// they're walked and never on either end. A spot on one of `avoid` (teleport
+// pads warp the player off before the split counts) moves further along the
+// path. Short paths get fewer
+pub fn place_checkpoints(
+    solution: &[(usize, usize)],
+    count: usize,
+    avoid: &[(usize, usize)],
+) -> Vec<(usize, usize)> {
+    let mut checkpoints: Vec<(usize, usize)> = Vec::new();
+    let Some(last) = solution.len().checked_sub(1) else {
+        return checkpoints;
+    };
+    let mut taken = 0;
+    for i in 1..=count {
+        let index = (i as f32 * last as f32 / (count + 1) as f32).round() as usize;
+        if let Some(index) =
+            (index.max(taken + 1)..last).find(|&index| !avoid.contains(&solution[index]))
+        {
+            checkpoints.push(solution[index]);
+            taken = index;
+        }
+    }
+    checkpoints
+}
+
+// A maze is too easy when the solution is short or passes few junctions (tiles
+// with three or more ways to go), i.e. it's close to a straight corridor
+pub fn is_too_easy(
+    tiles: &Vec2d<Tile>,
+    start: (usize, usize),
+    exit: (usize, usize),
+    difficulty: Difficulty,
+) -> bool {
+    let Some(path) = solve_astar(tiles, start, exit) else {
+        return false;
+    };
+    let min_len = ((tiles.cols + tiles.rows) as f32 * difficulty.min_length_factor()) as usize;
+    let junctions = path
+        .iter()
+        .filter(|&&pos| open_neighbors(tiles, pos).len() >= 3)
+        .count();
+    path.len() - 1 < min_len || junctions < difficulty.min_junctions()
+}
+
+// Every pair of orthogonally adjacent tiles once, (left/top, right/bottom),
+// whether or not there's a wall between them
+pub fn internal_walls(tiles: &Vec2d<Tile>) -> Vec<((usize, usize), (usize, usize))> {
+    let mut walls = Vec::with_capacity(
+        (tiles.rows.saturating_sub(1)) * tiles.cols + (tiles.cols.saturating_sub(1)) * tiles.rows,
+    );
+    for row in 0..tiles.rows {
+        for col in 0..tiles.cols {
+            if col + 1 < tiles.cols {
+                walls.push(((col, row), (col + 1, row)));
             }
-            3 => {
-                if row > 0 {
-                    Some((col, row - 1))
-                } else {
-                    None
-                }
+            if row + 1 < tiles.rows {
+                walls.push(((col, row), (col, row + 1)));
             }
-            _ => None,
-        };
+        }
+    }
+    // A wrapping maze also has walls across each edge
+    if tiles.wrap && tiles.cols >= MIN_WRAP_LEN {
+        walls.extend((0..tiles.rows).map(|row| ((tiles.cols - 1, row), (0, row))));
+    }
+    if tiles.wrap && tiles.rows >= MIN_WRAP_LEN {
+        walls.extend((0..tiles.cols).map(|col| ((col, tiles.rows - 1), (col, 0))));
+    }
+    walls
+}
 
-        if let Some((n_col, n_row)) = neighbor {
-            // Order tile coordinates for consistent HashSet lookup
-            let connection = if (col, row) < (n_col, n_row) {
-                ((col, row), (n_col, n_row))
-            } else {
-                ((n_col, n_row), (col, row))
-            };
+// True iff a and b are orthogonally adjacent with no wall between them
+pub fn are_connected(tiles: &Vec2d<Tile>, a: (usize, usize), b: (usize, usize)) -> bool {
+    if b.0 >= tiles.cols || b.1 >= tiles.rows || a.0 >= tiles.cols || a.1 >= tiles.rows {
+        return false;
+    }
+    let Some((wall_a, wall_b)) = shared_walls(tiles, a, b) else {
+        return false;
+    };
+    let open_a = !tiles.index(a.0, a.1).walls().contains(&wall_a);
+    let open_b = !tiles.index(b.0, b.1).walls().contains(&wall_b);
+    debug_assert_eq!(
+        open_a, open_b,
+        "tiles {:?} and {:?} disagree on their shared wall",
+        a, b
+    );
+    open_a && open_b
+}
+
+// Carve a perfect maze by removing walls in random order whenever they
+// separate two still unconnected regions
+pub fn kruskal(tiles: &mut Vec2d<Tile>) {
+    let mut walls = internal_walls(tiles);
+    walls.shuffle();
+    join_regions(tiles, walls);
+}
 
-            // Skip if we've already removed this wall
-            if removed_connections.contains(&connection) {
-                continue;
+// Remove each of `walls` in order that still separates two regions
+fn join_regions(tiles: &mut Vec2d<Tile>, walls: Vec<((usize, usize), (usize, usize))>) {
+    let mut sets = UnionFind::new(tiles.vec.len());
+    for (a, b) in walls {
+        if sets.union(a.1 * tiles.cols + a.0, b.1 * tiles.cols + b.0) {
+            remove_walls_between_positions(tiles, a, b);
+        }
+    }
+}
+
+// Independent cycles in the passage graph: open passages - tiles + regions
+pub fn count_loops(tiles: &Vec2d<Tile>) -> usize {
+    let mut sets = UnionFind::new(tiles.vec.len());
+    let mut passages = 0;
+    let mut regions = tiles.vec.len();
+    for (a, b) in internal_walls(tiles) {
+        if are_connected(tiles, a, b) {
+            passages += 1;
+            if sets.union(a.1 * tiles.cols + a.0, b.1 * tiles.cols + b.0) {
+                regions -= 1;
             }
+        }
+    }
+    passages + regions - tiles.vec.len()
+}
 
-            // Check if there's a wall between these tiles
-            let has_wall = match direction {
-                0 => tiles.index(col, row).walls.contains(&Wall::Right),
-                1 => tiles.index(col, row).walls.contains(&Wall::Bottom),
-                2 => tiles.index(col, row).walls.contains(&Wall::Left),
-                3 => tiles.index(col, row).walls.contains(&Wall::Top),
-                _ => false,
-            };
+// Adjacent pairs where one tile has the shared wall and the other doesn't
+pub fn wall_inconsistencies(tiles: &Vec2d<Tile>) -> Vec<((usize, usize), (usize, usize))> {
+    internal_walls(tiles)
+        .into_iter()
+        .filter(|&(a, b)| {
+            shared_walls(tiles, a, b).is_some_and(|(wall_a, wall_b)| {
+                tiles.index(a.0, a.1).walls().contains(&wall_a)
+                    != tiles.index(b.0, b.1).walls().contains(&wall_b)
+            })
+        })
+        .collect()
+}
+
+// Debug builds panic if any two neighbors disagree on their shared wall
+pub fn assert_wall_consistency(tiles: &Vec2d<Tile>) {
+    if cfg!(debug_assertions) {
+        let bad = wall_inconsistencies(tiles);
+        assert!(
+            bad.is_empty(),
+            "walls out of sync between {:?}\n{}",
+            bad,
+            debug_grid(tiles)
+        );
+    }
+}
+
+// Open rectangular chamber, all walls between its tiles removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Room {
+    pub col: usize,
+    pub row: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Room {
+    pub fn contains(&self, (col, row): (usize, usize)) -> bool {
+        col >= self.col
+            && col < self.col + self.width
+            && row >= self.row
+            && row < self.row + self.height
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.row..self.row + self.height)
+            .flat_map(move |row| (self.col..self.col + self.width).map(move |col| (col, row)))
+    }
+
+    // Rooms keep at least one tile of corridor between them
+    fn overlaps(&self, other: &Room) -> bool {
+        self.col < other.col + other.width + 1
+            && other.col < self.col + self.width + 1
+            && self.row < other.row + other.height + 1
+            && other.row < self.row + self.height + 1
+    }
+}
+
+// Place up to `count` non-overlapping rooms (sides between min_size and
+// max_size) and open them up, gives up on a room after a few failed spots
+pub fn carve_rooms(
+    tiles: &mut Vec2d<Tile>,
+    count: usize,
+    min_size: usize,
+    max_size: usize,
+) -> Vec<Room> {
+    const ATTEMPTS_PER_ROOM: usize = 10;
+    let min_size = min_size.max(2);
+    let mut rooms: Vec<Room> = Vec::new();
+    for _ in 0..count * ATTEMPTS_PER_ROOM {
+        if rooms.len() == count {
+            break;
+        }
+        let width = rand::gen_range(min_size, max_size.max(min_size) + 1);
+        let height = rand::gen_range(min_size, max_size.max(min_size) + 1);
+        if width >= tiles.cols || height >= tiles.rows {
+            continue;
+        }
+        let room = Room {
+            col: rand::gen_range(0, tiles.cols - width + 1),
+            row: rand::gen_range(0, tiles.rows - height + 1),
+            width,
+            height,
+        };
+        if rooms.iter().any(|other| room.overlaps(other)) {
+            continue;
+        }
+        for (col, row) in room.tiles() {
+            if col + 1 < room.col + room.width {
+                remove_walls_between_positions(tiles, (col, row), (col + 1, row));
+            }
+            if row + 1 < room.row + room.height {
+                remove_walls_between_positions(tiles, (col, row), (col, row + 1));
+            }
+        }
+        rooms.push(room);
+    }
+    rooms
+}
 
-            if has_wall {
-                // Remove the wall between tiles
-                remove_walls_between_positions(tiles, (col, row), (n_col, n_row));
-                removed_connections.insert(connection);
-                count += 1;
+// Fresh backtracker state: room tiles count as visited and their border
+// tiles are queued so corridors grow out of every room, returns the head
+pub fn prepare_carving(
+    tiles: &Vec2d<Tile>,
+    rooms: &[Room],
+    visited: &mut HashSet<(usize, usize)>,
+    stack: &mut Vec<(usize, usize)>,
+) -> (usize, usize) {
+    visited.clear();
+    stack.clear();
+    for room in rooms {
+        for pos in room.tiles() {
+            visited.insert(pos);
+            let (col, row) = pos;
+            let border = col == room.col
+                || row == room.row
+                || col + 1 == room.col + room.width
+                || row + 1 == room.row + room.height;
+            if border {
+                stack.push(pos);
             }
         }
     }
+    let free: Vec<(usize, usize)> = (0..tiles.vec.len())
+        .map(|i| tiles.coords(i))
+        .filter(|pos| !visited.contains(pos))
+        .collect();
+    let head = if free.is_empty() {
+        (
+            rand::gen_range(0, tiles.cols),
+            rand::gen_range(0, tiles.rows),
+        )
+    } else {
+        free[rand::gen_range(0, free.len())]
+    };
+    if !stack.is_empty() {
+        visited.insert(head);
+    }
+    head
+}
 
-    println!("Removed {} walls out of {} attempts", count, attempts);
+// Knock down walls between separate regions until every tile is reachable,
+// each room grows its own tree so this adds one door per extra region
+pub fn connect_regions(tiles: &mut Vec2d<Tile>) {
+    connect_regions_where(tiles, |_, _| true);
 }
 
-pub fn remove_walls_between_positions(
+// connect_regions through the walls between a and b that `allowed` accepts
+fn connect_regions_where(
     tiles: &mut Vec2d<Tile>,
-    pos1: (usize, usize),
-    pos2: (usize, usize),
+    allowed: impl Fn((usize, usize), (usize, usize)) -> bool,
 ) {
-    let (col1, row1) = pos1;
-    let (col2, row2) = pos2;
-
-    if row1 == row2 {
-        // Horizontal neighbors
-        if col1 < col2 {
-            tiles.index_mut(col1, row1).remove_wall(&Wall::Right);
-            tiles.index_mut(col2, row2).remove_wall(&Wall::Left);
-        } else {
-            tiles.index_mut(col1, row1).remove_wall(&Wall::Left);
-            tiles.index_mut(col2, row2).remove_wall(&Wall::Right);
+    let cols = tiles.cols;
+    let mut sets = UnionFind::new(tiles.vec.len());
+    let mut closed = Vec::new();
+    for (a, b) in internal_walls(tiles) {
+        if are_connected(tiles, a, b) {
+            sets.union(a.1 * cols + a.0, b.1 * cols + b.0);
+        } else if allowed(a, b) {
+            closed.push((a, b));
         }
-    } else {
-        // Vertical neighbors
-        if row1 < row2 {
-            tiles.index_mut(col1, row1).remove_wall(&Wall::Bottom);
-            tiles.index_mut(col2, row2).remove_wall(&Wall::Top);
-        } else {
-            tiles.index_mut(col1, row1).remove_wall(&Wall::Top);
-            tiles.index_mut(col2, row2).remove_wall(&Wall::Bottom);
+    }
+    closed.shuffle();
+    for (a, b) in closed {
+        if sets.union(a.1 * cols + a.0, b.1 * cols + b.0) {
+            remove_walls_between_positions(tiles, a, b);
         }
     }
 }
 
-fn get_unvisited_neighbors(
-    col: usize,
-    row: usize,
-    max_cols: usize,
-    max_rows: usize,
-    visited: &HashSet<(usize, usize)>,
-) -> Vec<(usize, usize)> {
-    let mut neighbors = Vec::with_capacity(4);
+// Tests only: a carved cols x rows maze (3 x 3 at least) whose bottom right
+// POCKET_SIZE square is walled off from the rest, an invalid maze for checking
+// that the validators refuse it
+#[cfg(test)]
+pub fn generate_with_isolated_region(cols: usize, rows: usize) -> Vec2d<Tile> {
+    assert!(
+        cols > POCKET_SIZE && rows > POCKET_SIZE,
+        "no room outside the pocket"
+    );
+    let mut tiles = blank_tiles(cols, rows);
+    carve(&mut tiles, Algorithm::default());
+    let inside =
+        |(col, row): (usize, usize)| col + POCKET_SIZE >= cols && row + POCKET_SIZE >= rows;
+    for (a, b) in internal_walls(&tiles) {
+        if inside(a) && inside(b) {
+            remove_walls_between_positions(&mut tiles, a, b);
+        } else if inside(a) != inside(b)
+            && let Some((wall_a, wall_b)) = shared_walls(&tiles, a, b)
+        {
+            tiles.set_wall(a, wall_a, true);
+            tiles.set_wall(b, wall_b, true);
+        }
+    }
+    // Sealing can cut paths that ran through the pocket, rejoin the outside
+    connect_regions_where(&mut tiles, |a, b| !inside(a) && !inside(b));
+    tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::teleport_pads;
+    use crate::solver::is_fully_connected;
+
+    type Pair = ((usize, usize), (usize, usize));
+    use crate::solver::compute_distances;
+    use crate::tile::WALLS;
 
-    // Check all four directions
-    if row > 0 && !visited.contains(&(col, row - 1)) {
-        neighbors.push((col, row - 1));
+    #[test]
+    fn removing_between_neighbors_opens_only_the_shared_wall() {
+        let pairs = [
+            ((0, 0), (1, 0), Wall::Right, Wall::Left),
+            ((0, 1), (1, 1), Wall::Right, Wall::Left),
+            ((0, 0), (0, 1), Wall::Bottom, Wall::Top),
+            ((1, 0), (1, 1), Wall::Bottom, Wall::Top),
+        ];
+        for (a, b, wall_a, wall_b) in pairs {
+            // Either argument order opens the same walls
+            for (first, second) in [(a, b), (b, a)] {
+                let mut tiles = blank_tiles(2, 2);
+                remove_walls_between_positions(&mut tiles, first, second);
+                for (col, row) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let mut expected: HashSet<Wall> = WALLS.into_iter().collect();
+                    if (col, row) == a {
+                        expected.remove(&wall_a);
+                    } else if (col, row) == b {
+                        expected.remove(&wall_b);
+                    }
+                    assert_eq!(
+                        *tiles.index(col, row).walls(),
+                        expected,
+                        "tile {:?} after opening {:?} to {:?}",
+                        (col, row),
+                        first,
+                        second
+                    );
+                }
+            }
+        }
     }
-    if row < max_rows - 1 && !visited.contains(&(col, row + 1)) {
-        neighbors.push((col, row + 1));
+
+    #[test]
+    fn exit_meets_the_minimum_solution_length() {
+        for seed in 0..20 {
+            rand::srand(seed);
+            let mut tiles = blank_tiles(10, 10);
+            carve(&mut tiles, Algorithm::default());
+            let exit = choose_exit_tile(&mut tiles, (0, 0), 15);
+            let distance = compute_distances(&tiles, (0, 0))
+                .index(exit.0, exit.1)
+                .unwrap();
+            assert!(
+                distance >= 15,
+                "seed {}: exit {} steps away",
+                seed,
+                distance
+            );
+            assert!(tiles.index(exit.0, exit.1).exit);
+        }
     }
-    if col > 0 && !visited.contains(&(col - 1, row)) {
-        neighbors.push((col - 1, row));
+
+    #[test]
+    fn dead_ends_of_a_hand_built_t() {
+        let mut tiles = blank_tiles(3, 2);
+        remove_walls_between_positions(&mut tiles, (0, 0), (1, 0));
+        remove_walls_between_positions(&mut tiles, (1, 0), (2, 0));
+        remove_walls_between_positions(&mut tiles, (1, 0), (1, 1));
+        let mut found = dead_ends(&tiles);
+        found.sort();
+        // The uncarved corners have four walls, they aren't dead ends
+        assert_eq!(found, vec![(0, 0), (1, 1), (2, 0)]);
     }
-    if col < max_cols - 1 && !visited.contains(&(col + 1, row)) {
-        neighbors.push((col + 1, row));
+
+    fn assert_unique_pairs(walls: &[Pair]) {
+        let mut unordered: Vec<Pair> = walls.iter().map(|&(a, b)| (a.min(b), a.max(b))).collect();
+        unordered.sort();
+        unordered.dedup();
+        assert_eq!(
+            unordered.len(),
+            walls.len(),
+            "duplicate pairs in {:?}",
+            walls
+        );
     }
 
-    neighbors
-}
+    #[test]
+    fn internal_walls_lists_every_pair_once() {
+        let (cols, rows) = (4, 3);
+        let walls = internal_walls(&blank_tiles(cols, rows));
+        assert_eq!(walls.len(), (rows - 1) * cols + (cols - 1) * rows);
+        assert_unique_pairs(&walls);
+    }
 
-pub fn choose_exit_tile(tiles: &mut Vec2d<Tile>) {
-    let col = rand::gen_range(0, tiles.cols);
-    let row = rand::gen_range(0, tiles.rows);
-    let tile = tiles.index_mut(col, row);
-    tile.exit = true;
-    tile.color = YELLOW;
+    #[test]
+    fn wrapped_grids_add_the_edge_pairs() {
+        let mut tiles = blank_tiles(3, 3);
+        tiles.wrap = true;
+        let walls = internal_walls(&tiles);
+        assert_eq!(walls.len(), 12 + 6);
+        assert_unique_pairs(&walls);
+
+        // Two columns are already neighbors, wrapping adds nothing across them
+        let mut tiles = blank_tiles(2, 3);
+        tiles.wrap = true;
+        let walls = internal_walls(&tiles);
+        assert_eq!(walls.len(), 7 + 2);
+        assert_unique_pairs(&walls);
+    }
+
+    #[test]
+    fn are_connected_needs_an_open_shared_wall() {
+        let mut tiles = blank_tiles(3, 2);
+        remove_walls_between_positions(&mut tiles, (0, 0), (1, 0));
+        assert!(are_connected(&tiles, (0, 0), (1, 0)));
+        assert!(are_connected(&tiles, (1, 0), (0, 0)));
+        // Adjacent but walled
+        assert!(!are_connected(&tiles, (1, 0), (2, 0)));
+        assert!(!are_connected(&tiles, (0, 0), (0, 1)));
+        // Not adjacent, diagonal or the same tile
+        assert!(!are_connected(&tiles, (0, 0), (2, 0)));
+        assert!(!are_connected(&tiles, (0, 0), (1, 1)));
+        assert!(!are_connected(&tiles, (0, 0), (0, 0)));
+        // Outside the grid
+        assert!(!are_connected(&tiles, (2, 0), (3, 0)));
+        assert!(!are_connected(&tiles, (0, 2), (0, 1)));
+    }
+
+    #[test]
+    fn one_sided_wall_removal_is_reported() {
+        let mut tiles = blank_tiles(2, 2);
+        remove_walls_between_positions(&mut tiles, (0, 0), (1, 0));
+        assert!(wall_inconsistencies(&tiles).is_empty());
+        assert_wall_consistency(&tiles);
+
+        tiles.set_wall((0, 1), Wall::Top, false);
+        assert_eq!(wall_inconsistencies(&tiles), vec![((0, 0), (0, 1))]);
+    }
+
+    // The assertion is compiled out of release builds
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "walls out of sync")]
+    fn consistency_assertion_catches_a_desync() {
+        let mut tiles = blank_tiles(2, 2);
+        tiles.set_wall((1, 0), Wall::Left, false);
+        assert_wall_consistency(&tiles);
+    }
+
+    #[test]
+    fn loop_count_reaches_the_target() {
+        for target in [0, 1, 5, 20] {
+            rand::srand(target as u64);
+            let mut tiles = blank_tiles(10, 10);
+            carve(&mut tiles, Algorithm::default());
+            assert_eq!(remove_walls_to_loop_count(&mut tiles, target), target);
+            assert_eq!(count_loops(&tiles), target);
+            assert_wall_consistency(&tiles);
+        }
+    }
+
+    #[test]
+    fn loop_count_stops_when_no_walls_are_left() {
+        rand::srand(3);
+        let mut tiles = blank_tiles(3, 3);
+        carve(&mut tiles, Algorithm::default());
+        // A 3x3 grid has 12 walls between its tiles, 8 of them carve the tree
+        assert_eq!(remove_walls_to_loop_count(&mut tiles, 100), 4);
+        assert_eq!(count_loops(&tiles), 4);
+    }
+
+    #[test]
+    fn restore_rerolls_from_the_same_base_maze() {
+        rand::srand(11);
+        let mut tiles = blank_tiles(8, 8);
+        carve(&mut tiles, Algorithm::default());
+        let base = snapshot_walls(&tiles);
+        remove_random_walls(&mut tiles, 0.05);
+        assert_ne!(snapshot_walls(&tiles), base);
+        restore_walls(&mut tiles, &base);
+        assert_eq!(snapshot_walls(&tiles), base);
+        assert_eq!(count_loops(&tiles), 0);
+        remove_random_walls(&mut tiles, 0.2);
+        restore_walls(&mut tiles, &base);
+        assert_eq!(snapshot_walls(&tiles), base);
+    }
+
+    #[test]
+    fn carve_with_gives_a_perfect_maze() {
+        for algorithm in Algorithm::ALL {
+            let rng = RandGenerator::new();
+            rng.srand(2);
+            let mut tiles = blank_tiles(9, 7);
+            carve_with(&mut tiles, algorithm, &rng);
+            assert_eq!(count_loops(&tiles), 0, "{:?}", algorithm);
+            assert!(crate::solver::is_fully_connected(&tiles), "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn budget_stops_carving_and_resumes() {
+        rand::srand(6);
+        let mut tiles = blank_tiles(30, 30);
+        let (mut visited, mut stack) = (HashSet::new(), Vec::new());
+        let mut head = prepare_carving(&tiles, &[], &mut visited, &mut stack);
+        // Every look at the clock takes a millisecond
+        let now = std::cell::Cell::new(0.0);
+        let clock = || {
+            now.set(now.get() + 0.001);
+            now.get()
+        };
+
+        head = generate_with_budget(&mut tiles, &mut visited, &mut stack, head, 4.5, clock);
+        // Four chunks fit in 4.5ms, the fifth check is over budget
+        assert!(visited.len() > 1 && visited.len() <= 4 * BUDGET_CHUNK_STEPS);
+        assert!(visited.len() < tiles.vec.len());
+
+        let mut frames = 1;
+        while visited.len() < tiles.vec.len() {
+            let before = visited.len();
+            head = generate_with_budget(&mut tiles, &mut visited, &mut stack, head, 4.5, clock);
+            assert!(visited.len() > before || visited.len() == tiles.vec.len());
+            frames += 1;
+            assert!(frames < 1000, "carving never finished");
+        }
+        assert_eq!(count_loops(&tiles), 0);
+        assert!(crate::solver::is_fully_connected(&tiles));
+    }
+
+    #[test]
+    fn four_rotations_give_the_original_walls() {
+        rand::srand(8);
+        let mut tiles = blank_tiles(5, 3);
+        carve(&mut tiles, Algorithm::default());
+        remove_n_random_walls(&mut tiles, 3);
+        let once = rotate_90(&tiles);
+        assert_eq!((once.cols, once.rows), (3, 5));
+        assert_ne!(snapshot_walls(&once), snapshot_walls(&tiles.transpose()));
+        let back = rotate_90(&rotate_90(&rotate_90(&once)));
+        assert_eq!((back.cols, back.rows), (5, 3));
+        assert_eq!(snapshot_walls(&back), snapshot_walls(&tiles));
+    }
+
+    #[test]
+    fn transforms_keep_the_maze_consistent_and_connected() {
+        rand::srand(12);
+        let mut tiles = blank_tiles(6, 4);
+        carve(&mut tiles, Algorithm::default());
+        let loops = count_loops(&tiles);
+        for (name, moved) in [
+            ("rotate", rotate_90(&tiles)),
+            ("mirror", mirror_horizontal(&tiles)),
+        ] {
+            assert!(wall_inconsistencies(&moved).is_empty(), "{}", name);
+            assert!(crate::solver::is_fully_connected(&moved), "{}", name);
+            assert_eq!(count_loops(&moved), loops, "{}", name);
+        }
+        let twice = mirror_horizontal(&mirror_horizontal(&tiles));
+        assert_eq!(snapshot_walls(&twice), snapshot_walls(&tiles));
+    }
+
+    #[test]
+    fn checkpoints_are_spread_evenly_along_the_solution() {
+        let solution: Vec<(usize, usize)> = (0..13).map(|col| (col, 0)).collect();
+        assert_eq!(
+            place_checkpoints(&solution, 3, &[]),
+            vec![(3, 0), (6, 0), (9, 0)]
+        );
+        // Too short for any, the ends never count
+        assert!(place_checkpoints(&solution[..2], 3, &[]).is_empty());
+    }
+
+    #[test]
+    fn checkpoints_skip_teleport_pads() {
+        let solution: Vec<(usize, usize)> = (0..13).map(|col| (col, 0)).collect();
+        let checkpoints = place_checkpoints(&solution, 3, &[(6, 0), (7, 0)]);
+        assert_eq!(checkpoints, vec![(3, 0), (8, 0), (9, 0)]);
+        let checkpoints = place_checkpoints(&solution, 3, &[(9, 0), (10, 0), (11, 0)]);
+        assert_eq!(checkpoints, vec![(3, 0), (6, 0)]);
+    }
+
+    #[test]
+    fn unreachable_minimum_falls_back_to_the_farthest_tile() {
+        rand::srand(1);
+        let mut tiles = blank_tiles(4, 4);
+        carve(&mut tiles, Algorithm::default());
+        let distances = compute_distances(&tiles, (0, 0));
+        let farthest = distances.vec.iter().flatten().max().copied().unwrap();
+        let exit = choose_exit_tile(&mut tiles, (0, 0), 1000);
+        assert_eq!(distances.index(exit.0, exit.1), &Some(farthest));
+    }
+
+    #[test]
+    fn concatenated_mazes_join_through_one_seam_wall() {
+        rand::srand(5);
+        let mut left = blank_tiles(3, 2);
+        carve(&mut left, Algorithm::default());
+        let mut right = blank_tiles(2, 2);
+        carve(&mut right, Algorithm::default());
+        let merged = concat_horizontal(&left, &right);
+        assert_eq!((merged.cols, merged.rows), (5, 2));
+        assert_eq!(merged.vec.len(), left.vec.len() + right.vec.len());
+        for (i, tile) in merged.vec.iter().enumerate() {
+            assert_eq!((tile.col, tile.row), (i % 5, i / 5));
+        }
+        let open_seams = (0..2)
+            .filter(|&row| are_connected(&merged, (2, row), (3, row)))
+            .count();
+        assert_eq!(open_seams, 1);
+        assert!(wall_inconsistencies(&merged).is_empty());
+        assert!(is_fully_connected(&merged));
+    }
+
+    #[test]
+    fn finished_mazes_are_fully_connected() {
+        rand::srand(9);
+        let variants = [
+            Settings::default(),
+            Settings {
+                perfect: true,
+                ..Settings::default()
+            },
+            Settings {
+                border_exit: true,
+                random_spawn: true,
+                min_solution_len: 5,
+                ..Settings::default()
+            },
+        ];
+        for (i, settings) in variants.iter().enumerate() {
+            for room_count in [0, 2] {
+                let mut tiles = blank_tiles(12, 10);
+                let rooms = carve_rooms(&mut tiles, room_count, 2, 3);
+                let (mut visited, mut stack) = (HashSet::new(), Vec::new());
+                let head = prepare_carving(&tiles, &rooms, &mut visited, &mut stack);
+                iterative_backtracking(&mut tiles, &mut visited, &mut stack, head, 0);
+                let target_loops = (i == 0).then_some(3);
+                let (_, entrance, exit) =
+                    finish_generation(&mut tiles, !rooms.is_empty(), target_loops, settings);
+                assert!(
+                    is_fully_connected(&tiles),
+                    "settings {} rooms {}",
+                    i,
+                    room_count
+                );
+                assert!(wall_inconsistencies(&tiles).is_empty());
+                assert_ne!(entrance, exit);
+                assert!(tiles.index(exit.0, exit.1).exit);
+            }
+        }
+    }
+
+    #[test]
+    fn border_exit_is_the_farthest_border_tile() {
+        for seed in 0..5 {
+            rand::srand(seed);
+            let mut tiles = blank_tiles(7, 6);
+            carve(&mut tiles, Algorithm::default());
+            let start = (3, 2);
+            let exit = choose_border_exit(&mut tiles, start).unwrap();
+            let border = border_walls(&tiles, exit);
+            assert!(!border.is_empty(), "{:?} is inside", exit);
+            let tile = tiles.index(exit.0, exit.1);
+            assert!(tile.exit);
+            assert!(border.iter().any(|wall| !tile.walls().contains(wall)));
+
+            let distances = compute_distances(&tiles, start);
+            let farthest = (0..tiles.vec.len())
+                .map(|i| tiles.coords(i))
+                .filter(|&pos| !border_walls(&tiles, pos).is_empty())
+                .filter_map(|pos| *distances.index(pos.0, pos.1))
+                .max();
+            assert_eq!(*distances.index(exit.0, exit.1), farthest);
+        }
+        let mut wrapped = blank_tiles(4, 4);
+        wrapped.wrap = true;
+        assert_eq!(choose_border_exit(&mut wrapped, (0, 0)), None);
+    }
+
+    #[test]
+    fn random_spawn_is_reachable_and_never_the_exit() {
+        for seed in 0..40 {
+            rand::srand(seed);
+            let mut tiles = blank_tiles(6, 5);
+            carve(&mut tiles, Algorithm::default());
+            remove_n_random_walls(&mut tiles, 4);
+            let exit = (rand::gen_range(0, 6), rand::gen_range(0, 5));
+            let spawn = random_spawn(&tiles, exit);
+            assert_ne!(spawn, exit, "seed {}", seed);
+            assert!(solve_astar(&tiles, spawn, exit).is_some(), "seed {}", seed);
+        }
+        // A lone tile has nowhere else to go
+        assert_eq!(random_spawn(&blank_tiles(1, 1), (0, 0)), (0, 0));
+    }
+
+    #[test]
+    fn thick_corridors_are_width_tiles_wide() {
+        rand::srand(4);
+        let width = 3;
+        let tiles = generate_thick(4, 3, width);
+        assert_eq!((tiles.cols, tiles.rows), (12, 9));
+        assert!(is_fully_connected(&tiles));
+        // Across every seam between two cells the opening is all or nothing
+        let mut openings = 0;
+        for cell_row in 0..3 {
+            for cell_col in 0..4 {
+                let (col, row) = (cell_col * width, cell_row * width);
+                if cell_col + 1 < 4 {
+                    let open = (row..row + width)
+                        .filter(|&r| are_connected(&tiles, (col + width - 1, r), (col + width, r)))
+                        .count();
+                    assert!(open == 0 || open == width, "{} open", open);
+                    openings += usize::from(open > 0);
+                }
+                if cell_row + 1 < 3 {
+                    let open = (col..col + width)
+                        .filter(|&c| are_connected(&tiles, (c, row + width - 1), (c, row + width)))
+                        .count();
+                    assert!(open == 0 || open == width, "{} open", open);
+                    openings += usize::from(open > 0);
+                }
+            }
+        }
+        // The logical maze is perfect, a tree over its 12 cells
+        assert_eq!(openings, 11);
+    }
+
+    #[test]
+    fn screen_points_map_to_the_tile_under_them() {
+        // 25px tiles, the grid is 100 x 75 starting at (0, 13)
+        let mut tiles = blank_tiles(4, 3);
+        fit_tiles_to_screen(&mut tiles, 100.0, 100.0);
+        assert_eq!(tile_at_screen(&tiles, 37.5, 75.5), Some((1, 2)));
+        assert_eq!(tile_at_screen(&tiles, 12.5, 25.5), Some((0, 0)));
+        // Tiles own their top and left edges
+        assert_eq!(tile_at_screen(&tiles, 25.0, 13.0), Some((1, 0)));
+        assert_eq!(tile_at_screen(&tiles, 24.9, 37.9), Some((0, 0)));
+        assert_eq!(tile_at_screen(&tiles, 99.9, 87.9), Some((3, 2)));
+        for (x, y) in [(50.0, 12.9), (100.0, 50.0), (-0.1, 50.0), (50.0, 88.0)] {
+            assert_eq!(tile_at_screen(&tiles, x, y), None, "({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn start_flow_opens_a_dead_end_start() {
+        for seed in 0..10 {
+            rand::srand(seed);
+            let mut tiles = blank_tiles(6, 6);
+            carve(&mut tiles, Algorithm::default());
+            let start = dead_ends(&tiles)[0];
+            ensure_start_flow(&mut tiles, start);
+            assert!(
+                tiles.index(start.0, start.1).walls().len() <= 2,
+                "seed {}",
+                seed
+            );
+            for (col, row) in open_neighbors(&tiles, start) {
+                assert!(tiles.index(col, row).walls().len() <= 2, "seed {}", seed);
+            }
+            assert!(wall_inconsistencies(&tiles).is_empty());
+        }
+    }
+
+    #[test]
+    fn braiding_opens_the_requested_share_of_dead_ends() {
+        for seed in 0..5 {
+            rand::srand(seed);
+            let mut tiles = blank_tiles(10, 8);
+            carve(&mut tiles, Algorithm::default());
+            let perfect = snapshot_walls(&tiles);
+            let before = dead_ends(&tiles).len();
+            for share in [0.0, 0.5, 1.0] {
+                restore_walls(&mut tiles, &perfect);
+                braid(&mut tiles, share);
+                let asked = (before as f32 * share).round() as usize;
+                assert!(
+                    dead_ends(&tiles).len() <= before - asked,
+                    "seed {} share {}",
+                    seed,
+                    share
+                );
+            }
+            assert!(dead_ends(&tiles).is_empty());
+            assert_ne!(snapshot_walls(&tiles), perfect);
+            // Toggling back off puts every wall where it was
+            restore_walls(&mut tiles, &perfect);
+            assert_eq!(snapshot_walls(&tiles), perfect);
+            assert_eq!(dead_ends(&tiles).len(), before);
+        }
+    }
+
+    #[test]
+    fn same_seed_removes_the_same_walls() {
+        rand::srand(8);
+        let mut tiles = blank_tiles(8, 8);
+        carve(&mut tiles, Algorithm::default());
+        let removed_with = |seed: u64| {
+            let mut copy = tiles.clone();
+            let rng = RandGenerator::new();
+            rng.srand(seed);
+            assert_eq!(remove_n_walls_with(&mut copy, 6, &rng), 6);
+            snapshot_walls(&copy)
+        };
+        assert_eq!(removed_with(42), removed_with(42));
+        assert_ne!(removed_with(42), removed_with(43));
+    }
+
+    #[test]
+    fn exactly_n_walls_come_down_while_there_are_enough() {
+        let closed = |tiles: &Vec2d<Tile>| {
+            internal_walls(tiles)
+                .into_iter()
+                .filter(|&(a, b)| !are_connected(tiles, a, b))
+                .count()
+        };
+        let mut tiles = blank_tiles(3, 3);
+        assert_eq!(closed(&tiles), 12);
+        assert_eq!(remove_n_random_walls(&mut tiles, 5), 5);
+        assert_eq!(closed(&tiles), 7);
+        assert_eq!(remove_n_random_walls(&mut tiles, 0), 0);
+        // Asking for more than stand takes them all
+        assert_eq!(remove_n_random_walls(&mut tiles, 100), 7);
+        assert_eq!(closed(&tiles), 0);
+        assert_eq!(remove_n_random_walls(&mut tiles, 1), 0);
+    }
+
+    // Corridor along the top of a 7x2 grid with a tooth hanging down from
+    // each of `teeth`, every tooth makes its top tile a junction
+    fn comb(teeth: &[usize]) -> Vec2d<Tile> {
+        let mut tiles = blank_tiles(7, 2);
+        for col in 1..7 {
+            remove_walls_between_positions(&mut tiles, (col - 1, 0), (col, 0));
+        }
+        for &col in teeth {
+            remove_walls_between_positions(&mut tiles, (col, 0), (col, 1));
+        }
+        tiles
+    }
+
+    #[test]
+    fn too_easy_around_the_length_and_junction_thresholds() {
+        // Normal wants (7 + 2) / 2 = 4 steps and 2 junctions
+        let normal = Difficulty::Normal;
+        let all = comb(&[1, 2, 3, 4, 5]);
+        assert!(!is_too_easy(&all, (0, 0), (4, 0), normal));
+        assert!(is_too_easy(&all, (0, 0), (3, 0), normal));
+        assert!(!is_too_easy(&comb(&[2, 4]), (0, 0), (6, 0), normal));
+        assert!(is_too_easy(&comb(&[2]), (0, 0), (6, 0), normal));
+        // A straight corridor passes on Easy, Hard wants 9 steps
+        assert!(!is_too_easy(&comb(&[]), (0, 0), (6, 0), Difficulty::Easy));
+        assert!(is_too_easy(&all, (0, 0), (6, 0), Difficulty::Hard));
+        // Unsolvable mazes are another problem
+        assert!(!is_too_easy(&blank_tiles(7, 2), (0, 0), (6, 0), normal));
+    }
+
+    #[test]
+    fn rooms_are_open_inside_and_joined_to_the_maze() {
+        for seed in 0..5 {
+            rand::srand(seed);
+            let mut tiles = blank_tiles(16, 12);
+            let rooms = carve_rooms(&mut tiles, 3, ROOM_MIN_SIZE, ROOM_MAX_SIZE);
+            assert!(!rooms.is_empty(), "seed {}", seed);
+            for room in &rooms {
+                for (col, row) in room.tiles() {
+                    for next in [(col + 1, row), (col, row + 1)] {
+                        if room.contains(next) {
+                            assert!(are_connected(&tiles, (col, row), next));
+                        }
+                    }
+                }
+            }
+            for (i, room) in rooms.iter().enumerate() {
+                assert!(rooms[i + 1..].iter().all(|other| !room.overlaps(other)));
+            }
+            let (mut visited, mut stack) = (HashSet::new(), Vec::new());
+            let head = prepare_carving(&tiles, &rooms, &mut visited, &mut stack);
+            iterative_backtracking(&mut tiles, &mut visited, &mut stack, head, 0);
+            connect_regions(&mut tiles);
+            assert!(is_fully_connected(&tiles), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn fixed_tile_px_covers_the_screen_or_the_configured_count() {
+        // Big tiles keep NUMBER_OF_TILES_IN_BIGGER_AXIS along the bigger axis
+        let tiles = layout_tiles(1000.0, 500.0, Some(50.0));
+        assert_eq!((tiles.cols, tiles.rows), (30, 15));
+        assert_eq!(tiles.vec[0].width, 50.0);
+        assert_eq!(maze_bounds(&tiles), Rect::new(0.0, 0.0, 1500.0, 750.0));
+        // Small ones take ceil(screen / tile) to cover the screen
+        let tiles = layout_tiles(1000.0, 505.0, Some(10.0));
+        assert_eq!((tiles.cols, tiles.rows), (100, 51));
+        assert_eq!(tiles.vec[0].width, 10.0);
+    }
+
+    #[test]
+    fn tiny_windows_keep_the_minimum_tile_size_and_scroll() {
+        // 160 / 30 tiles would be under MIN_TILE_PX
+        let tiles = layout_tiles(160.0, 100.0, None);
+        assert_eq!(tiles.vec[0].width, MIN_TILE_PX);
+        assert_eq!((tiles.cols, tiles.rows), (30, 19));
+        let bounds = maze_bounds(&tiles);
+        assert!(bounds.w > 160.0 && bounds.h > 100.0);
+        assert_eq!((bounds.x, bounds.y), (0.0, 0.0));
+        // Big enough windows still fit the grid
+        let tiles = layout_tiles(600.0, 400.0, None);
+        assert_eq!(tiles.vec[0].width, 20.0);
+        assert!(maze_bounds(&tiles).w <= 600.0);
+    }
+
+    // Left, right, top and bottom margins of a grid on an s_w x s_h screen
+    fn margins(tiles: &Vec2d<Tile>, s_w: f32, s_h: f32) -> [f32; 4] {
+        let bounds = maze_bounds(tiles);
+        [
+            bounds.x,
+            s_w - bounds.right(),
+            bounds.y,
+            s_h - bounds.bottom(),
+        ]
+    }
+
+    #[test]
+    fn letterboxed_margins_match_within_a_pixel() {
+        for (s_w, s_h) in [
+            (1000.0, 617.0),
+            (801.0, 599.0),
+            (500.0, 1003.0),
+            (640.0, 640.0),
+        ] {
+            let tiles = layout_tiles_letterboxed(s_w, s_h);
+            let [left, right, top, bottom] = margins(&tiles, s_w, s_h);
+            assert!((left - right).abs() <= 1.0, "{} x {}", s_w, s_h);
+            assert!((top - bottom).abs() <= 1.0, "{} x {}", s_w, s_h);
+            assert!(left.min(right).min(top).min(bottom) >= 0.0);
+            let tile = &tiles.vec[0];
+            assert_eq!(tile.width, tile.height);
+            assert_eq!(tile.width, tile.width.floor());
+        }
+    }
+
+    #[test]
+    fn centered_layouts_split_odd_remainders_evenly() {
+        assert_eq!(centered_start(101.0, 90.0), 6.0);
+        assert_eq!(centered_start(100.0, 90.0), 5.0);
+        assert_eq!(centered_start(80.0, 90.0), 0.0);
+        for (s_w, s_h) in [(1013.0, 611.0), (1000.0, 600.0), (777.0, 333.0)] {
+            let tiles = layout_tiles(s_w, s_h, None);
+            let [left, right, top, bottom] = margins(&tiles, s_w, s_h);
+            assert!((left - right).abs() <= 1.0, "{} x {}", s_w, s_h);
+            assert!((top - bottom).abs() <= 1.0, "{} x {}", s_w, s_h);
+            // Every tile sits on the first one's whole pixel grid
+            let first = tiles.vec[0].screen_position;
+            assert_eq!(first, first.round());
+            for tile in &tiles.vec {
+                let expected = first + vec2(tile.col as f32, tile.row as f32) * tile.width;
+                assert_eq!(tile.screen_position, expected);
+                assert_eq!(
+                    tile_at_screen(&tiles, expected.x, expected.y),
+                    Some((tile.col, tile.row))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bounds_are_the_union_of_the_tile_rects() {
+        let mut tiles = blank_tiles(3, 2);
+        for tile in &mut tiles.vec {
+            tile.screen_position = vec2(7.0 + tile.col as f32 * 4.0, 11.0 + tile.row as f32 * 6.0);
+            tile.width = 4.0;
+            tile.height = 6.0;
+        }
+        let union = tiles
+            .vec
+            .iter()
+            .map(|tile| {
+                Rect::new(
+                    tile.screen_position.x,
+                    tile.screen_position.y,
+                    tile.width,
+                    tile.height,
+                )
+            })
+            .reduce(|a, b| a.combine_with(b))
+            .unwrap();
+        assert_eq!(maze_bounds(&tiles), union);
+        assert_eq!(union, Rect::new(7.0, 11.0, 12.0, 12.0));
+        assert_eq!(maze_bounds(&Vec2d::new(Vec::new(), 0, 0)), Rect::default());
+    }
+
+    #[test]
+    fn teleport_pads_are_distinct_and_off_the_avoided_tiles() {
+        rand::srand(6);
+        let tiles = blank_tiles(4, 3);
+        let avoid = [(0, 0), (3, 2)];
+        let teleports = place_teleports(&tiles, 3, &avoid);
+        assert_eq!(teleports.len(), 3);
+        let pads: HashSet<(usize, usize)> = teleport_pads(&teleports).into_iter().collect();
+        assert_eq!(pads.len(), 6);
+        assert!(avoid.iter().all(|pos| !pads.contains(pos)));
+        // Ten free tiles hold five pairs at most
+        assert_eq!(place_teleports(&tiles, 9, &avoid).len(), 5);
+    }
+
+    #[test]
+    fn traps_stay_off_the_solution_and_clear_of_the_ends() {
+        for seed in 0..5 {
+            rand::srand(seed);
+            let mut tiles = blank_tiles(10, 10);
+            carve(&mut tiles, Algorithm::default());
+            let (start, exit) = ((0, 0), (9, 9));
+            let avoid = [(5, 5)];
+            let traps = place_traps(&tiles, 4, start, exit, &avoid);
+            assert_eq!(traps.len(), 4);
+            let solution = solve_astar(&tiles, start, exit).unwrap();
+            for &trap in &traps {
+                assert!(!solution.contains(&trap) && !avoid.contains(&trap));
+                assert!(manhattan(&tiles, trap, start) >= TRAP_CLEARANCE);
+                assert!(manhattan(&tiles, trap, exit) >= TRAP_CLEARANCE);
+            }
+        }
+    }
+
+    #[test]
+    fn carved_fraction_runs_from_zero_to_one() {
+        assert_eq!(carved_fraction(0, 40), 0.0);
+        assert_eq!(carved_fraction(10, 40), 0.25);
+        assert_eq!(carved_fraction(40, 40), 1.0);
+        // Never past done, and nothing to carve is done already
+        assert_eq!(carved_fraction(41, 40), 1.0);
+        assert_eq!(carved_fraction(0, 0), 1.0);
+    }
+
+    #[test]
+    fn perfect_mode_keeps_every_tree_wall() {
+        let remaining = |tiles: &Vec2d<Tile>| {
+            internal_walls(tiles)
+                .into_iter()
+                .filter(|&(a, b)| !are_connected(tiles, a, b))
+                .count()
+        };
+        let perfect = Settings {
+            perfect: true,
+            ..Settings::default()
+        };
+        rand::srand(17);
+        let mut tiles = blank_tiles(9, 7);
+        carve(&mut tiles, Algorithm::default());
+        let total = internal_walls(&tiles).len();
+        add_loops(&mut tiles, (0, 0), None, &perfect);
+        assert_eq!(remaining(&tiles), total - (9 * 7 - 1));
+        assert_eq!(count_loops(&tiles), 0);
+        // Without the flag every wall opened past the tree adds a loop
+        add_loops(&mut tiles, (0, 0), Some(3), &Settings::default());
+        let loops = count_loops(&tiles);
+        assert!(loops >= 3);
+        assert_eq!(remaining(&tiles), total - (9 * 7 - 1) - loops);
+    }
+
+    #[test]
+    fn single_steps_finish_generation() {
+        rand::srand(23);
+        let mut tiles = blank_tiles(6, 5);
+        let (mut visited, mut stack) = (HashSet::new(), Vec::new());
+        let mut head = (0, 0);
+        let mut presses = 0;
+        while visited.len() != tiles.vec.len() {
+            // The first press also marks the start as visited
+            let before = visited.len().max(1);
+            head = iterative_backtracking(&mut tiles, &mut visited, &mut stack, head, 1);
+            presses += 1;
+            // One carve or one step back per press
+            assert!(visited.len() - before <= 1);
+            // Every tile is carved to once and backed out of at most once
+            assert!(presses <= 2 * tiles.vec.len());
+        }
+        assert!(is_fully_connected(&tiles));
+        assert_eq!(count_loops(&tiles), 0);
+    }
 }
@@ -0,0 +1,148 @@
+use crate::blocks::block_grid;
+use crate::theme::Theme;
+use crate::tile::Tile;
+use crate::utils::Vec2d;
+
+use macroquad::prelude::*;
+use std::collections::HashSet;
+
+// Largest share of the screen width or height the minimap may take
+const MINIMAP_SHARE: f32 = 0.25;
+// Gap to the screen edge, the top one leaves room for the FPS counter
+const MINIMAP_MARGIN: f32 = 10.0;
+const MINIMAP_TOP: f32 = 30.0;
+
+// Top right corner rect for a cols x rows maze, keeping its aspect ratio
+pub fn minimap_rect(cols: usize, rows: usize, screen_w: f32, screen_h: f32) -> Rect {
+    let (blocks_w, blocks_h) = ((cols * 2 + 1) as f32, (rows * 2 + 1) as f32);
+    let scale = (screen_w * MINIMAP_SHARE / blocks_w).min(screen_h * MINIMAP_SHARE / blocks_h);
+    let (w, h) = (blocks_w * scale, blocks_h * scale);
+    Rect::new(screen_w - w - MINIMAP_MARGIN, MINIMAP_TOP, w, h)
+}
+
+// Part of the minimap `rect` showing tile (col, row), the floor block between
+// its walls on the block grid
+pub fn minimap_tile_rect(rect: Rect, cols: usize, rows: usize, (col, row): (usize, usize)) -> Rect {
+    let block_w = rect.w / (cols * 2 + 1) as f32;
+    let block_h = rect.h / (rows * 2 + 1) as f32;
+    Rect::new(
+        rect.x + (col * 2 + 1) as f32 * block_w,
+        rect.y + (row * 2 + 1) as f32 * block_h,
+        block_w,
+        block_h,
+    )
+}
+
+// Small overview of the whole maze. The walls are baked into a texture that is
+// only rebuilt when they change, the overlays are a few rects per frame
+#[derive(Debug, Default)]
+pub struct Minimap {
+    texture: Option<Texture2D>,
+    // Revision of the tiles the texture was built from
+    revision: Option<u64>,
+}
+
+impl Minimap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn refresh(&mut self, tiles: &Vec2d<Tile>) {
+        if self.revision == Some(tiles.revision) {
+            return;
+        }
+        let blocks = block_grid(tiles);
+        let mut image = Image::gen_image_color(blocks.cols as u16, blocks.rows as u16, BLANK);
+        for (i, _) in blocks.vec.iter().enumerate().filter(|(_, wall)| **wall) {
+            let (x, y) = blocks.coords(i);
+            image.set_pixel(x as u32, y as u32, WHITE);
+        }
+        let texture = Texture2D::from_image(&image);
+        texture.set_filter(FilterMode::Nearest);
+        self.texture = Some(texture);
+        self.revision = Some(tiles.revision);
+    }
+
+    // Draw in screen space, `explored` and `solution` are only overlaid when given
+    pub fn draw(
+        &mut self,
+        tiles: &Vec2d<Tile>,
+        theme: &Theme,
+        player: (usize, usize),
+        explored: Option<&HashSet<(usize, usize)>>,
+        solution: Option<&[(usize, usize)]>,
+    ) {
+        self.refresh(tiles);
+        let rect = minimap_rect(tiles.cols, tiles.rows, screen_width(), screen_height());
+        let tile_rect = |pos| minimap_tile_rect(rect, tiles.cols, tiles.rows, pos);
+        let fill = |pos, color| {
+            let r: Rect = tile_rect(pos);
+            draw_rectangle(r.x, r.y, r.w, r.h, color);
+        };
+
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, theme.path_color);
+        if let Some(explored) = explored {
+            for &pos in explored {
+                fill(pos, theme.visited_color);
+            }
+        }
+        if let Some(texture) = &self.texture {
+            draw_texture_ex(
+                texture,
+                rect.x,
+                rect.y,
+                theme.wall_color,
+                DrawTextureParams {
+                    dest_size: Some(rect.size()),
+                    ..Default::default()
+                },
+            );
+        }
+        if let Some(path) = solution {
+            let thickness = (tile_rect((0, 0)).w / 2.0).max(1.0);
+            // Skip the jumps across the edge of a wrapping maze
+            let adjacent = |(a, b): ((usize, usize), (usize, usize))| {
+                a.0.abs_diff(b.0) + a.1.abs_diff(b.1) == 1
+            };
+            for pair in path.windows(2).filter(|pair| adjacent((pair[0], pair[1]))) {
+                let (a, b) = (tile_rect(pair[0]).center(), tile_rect(pair[1]).center());
+                draw_line(a.x, a.y, b.x, b.y, thickness, WHITE);
+            }
+        }
+        if let Some(exit) = tiles.vec.iter().find(|tile| tile.exit) {
+            fill((exit.col, exit.row), theme.exit_color);
+        }
+        fill(player, theme.player_color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_rects_sit_on_the_floor_blocks() {
+        // 2 x 1 maze, 5 x 3 blocks of 10 px
+        let rect = Rect::new(100.0, 30.0, 50.0, 30.0);
+        assert_eq!(
+            minimap_tile_rect(rect, 2, 1, (0, 0)),
+            Rect::new(110.0, 40.0, 10.0, 10.0)
+        );
+        assert_eq!(
+            minimap_tile_rect(rect, 2, 1, (1, 0)),
+            Rect::new(130.0, 40.0, 10.0, 10.0)
+        );
+        // The last tile leaves one wall block before the edge
+        let last = minimap_tile_rect(rect, 2, 1, (1, 0));
+        assert_eq!(rect.right() - last.right(), 10.0);
+    }
+
+    #[test]
+    fn minimap_keeps_the_maze_aspect_ratio() {
+        let rect = minimap_rect(10, 5, 800.0, 600.0);
+        assert!((rect.w / rect.h - 21.0 / 11.0).abs() < 1e-4);
+        assert!(rect.w <= 800.0 * MINIMAP_SHARE && rect.h <= 600.0 * MINIMAP_SHARE);
+        assert_eq!(rect.right(), 800.0 - MINIMAP_MARGIN);
+        assert_eq!(rect.y, MINIMAP_TOP);
+    }
+}
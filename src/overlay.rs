@@ -0,0 +1,304 @@
+use crate::maze::maze_bounds;
+use crate::player::{Direction, Teleport};
+use crate::solver::open_neighbors;
+use crate::tile::Tile;
+use crate::utils::Vec2d;
+
+use macroquad::prelude::*;
+use std::collections::HashSet;
+
+// Narrowest tile (in pixels) that still fits readable coordinates
+pub const MIN_COORD_TILE_PX: f32 = 28.0;
+
+// Tint every tile along a path
+pub fn draw_path(tiles: &Vec2d<Tile>, path: &[(usize, usize)], color: Color) {
+    for &(col, row) in path {
+        let tile = tiles.index(col, row);
+        let inset = tile.width * 0.3;
+        draw_rectangle(
+            tile.screen_position.x + inset,
+            tile.screen_position.y + inset,
+            tile.width - inset * 2.0,
+            tile.height - inset * 2.0,
+            color,
+        );
+    }
+}
+
+// Tint whole tiles, e.g. to highlight dead ends
+pub fn tint_tiles(tiles: &Vec2d<Tile>, coords: &[(usize, usize)], color: Color) {
+    for &(col, row) in coords {
+        let tile = tiles.index(col, row);
+        draw_rectangle(
+            tile.screen_position.x,
+            tile.screen_position.y,
+            tile.width,
+            tile.height,
+            color,
+        );
+    }
+}
+
+// Tiles on the backtracker's stack, the path it is still extending, once each
+// and without the carving head which has its own tint
+pub fn stack_tiles(stack: &[(usize, usize)], head: (usize, usize)) -> Vec<(usize, usize)> {
+    let mut seen = HashSet::new();
+    stack
+        .iter()
+        .copied()
+        .filter(|&pos| pos != head && seen.insert(pos))
+        .collect()
+}
+
+// Tiles the backtracker has reached in grid order, to shade while it is still
+// carving. None once every tile is visited
+pub fn explored_shading(visited: &HashSet<(usize, usize)>, total: usize) -> Vec<(usize, usize)> {
+    if visited.len() >= total {
+        return Vec::new();
+    }
+    let mut explored: Vec<(usize, usize)> = visited.iter().copied().collect();
+    explored.sort_unstable_by_key(|&(col, row)| (row, col));
+    explored
+}
+
+// Square outline inside a tile, marks it by shape as well as color
+pub fn draw_tile_marker(tiles: &Vec2d<Tile>, (col, row): (usize, usize), color: Color) {
+    let tile = tiles.index(col, row);
+    let inset = tile.width.min(tile.height) * 0.2;
+    draw_rectangle_lines(
+        tile.screen_position.x + inset,
+        tile.screen_position.y + inset,
+        tile.width - inset * 2.0,
+        tile.height - inset * 2.0,
+        (tile.width * 0.1).max(2.0),
+        color,
+    );
+}
+
+// Thin bar along the bottom of the screen, filled to `fraction`
+pub fn draw_progress_bar(fraction: f32, color: Color) {
+    let height = (screen_height() * 0.01).max(4.0);
+    let y = screen_height() - height;
+    draw_rectangle(
+        0.0,
+        y,
+        screen_width(),
+        height,
+        Color::new(1.0, 1.0, 1.0, 0.15),
+    );
+    draw_rectangle(
+        0.0,
+        y,
+        screen_width() * fraction.clamp(0.0, 1.0),
+        height,
+        color,
+    );
+}
+
+// Faint cross on each trap, easy to miss at speed
+pub fn draw_traps(tiles: &Vec2d<Tile>, traps: &HashSet<(usize, usize)>, color: Color) {
+    for &(col, row) in traps {
+        let tile = tiles.index(col, row);
+        let inset = tile.width * 0.3;
+        let (x0, y0) = (
+            tile.screen_position.x + inset,
+            tile.screen_position.y + inset,
+        );
+        let (x1, y1) = (
+            tile.screen_position.x + tile.width - inset,
+            tile.screen_position.y + tile.height - inset,
+        );
+        draw_line(x0, y0, x1, y1, 1.0, color);
+        draw_line(x0, y1, x1, y0, 1.0, color);
+    }
+}
+
+// Arrow from the center of `from` pointing one tile in `direction`
+pub fn draw_hint_arrow(
+    tiles: &Vec2d<Tile>,
+    from: (usize, usize),
+    direction: Direction,
+    color: Color,
+) {
+    let Some(direction) = direction.delta() else {
+        return;
+    };
+    let tile = tiles.index(from.0, from.1);
+    let size = tile.width;
+    let start = tile.screen_position + vec2(tile.width, tile.height) / 2.0;
+    let tip = start + direction * size * 0.8;
+    let side = direction.perp() * size * 0.15;
+    let base = tip - direction * size * 0.3;
+    draw_line(start.x, start.y, base.x, base.y, size * 0.08, color);
+    draw_triangle(tip, base + side, base - side, color);
+}
+
+// Each teleport pair gets its own color so linked pads can be matched up
+pub fn draw_teleports(tiles: &Vec2d<Tile>, teleports: &[Teleport]) {
+    const PAD_COLORS: [Color; 4] = [MAGENTA, SKYBLUE, LIME, ORANGE];
+    for (i, &(a, b)) in teleports.iter().enumerate() {
+        let color = PAD_COLORS[i % PAD_COLORS.len()];
+        for (col, row) in [a, b] {
+            let tile = tiles.index(col, row);
+            draw_circle_lines(
+                tile.screen_position.x + tile.width / 2.0,
+                tile.screen_position.y + tile.height / 2.0,
+                tile.width * 0.35,
+                tile.width * 0.08,
+                color,
+            );
+        }
+    }
+}
+
+// Grid lines on the tile lattice, extended over the margins around the maze
+pub fn draw_background_grid(tiles: &Vec2d<Tile>, color: Color) {
+    let Some(first) = tiles.vec.first() else {
+        return;
+    };
+    let size = first.width;
+    let origin = first.screen_position;
+    let bounds = maze_bounds(tiles);
+    let right = screen_width().max(bounds.right());
+    let bottom = screen_height().max(bounds.bottom());
+
+    for x in grid_lines(origin.x, size, right) {
+        draw_line(x, 0.0, x, bottom, 1.0, color);
+    }
+    for y in grid_lines(origin.y, size, bottom) {
+        draw_line(0.0, y, right, y, 1.0, color);
+    }
+}
+
+// Lattice lines along one axis from 0 up to `end`, one every `size` px and
+// through `origin`, the first tile's edge
+pub fn grid_lines(origin: f32, size: f32, end: f32) -> Vec<f32> {
+    // Step back from the first tile to the first line on screen
+    let first = origin - (origin / size).floor() * size;
+    (0..)
+        .map(|i| first + i as f32 * size)
+        .take_while(|&line| line <= end)
+        .collect()
+}
+
+// Whether tiles `tile_width` px wide are big enough for draw_tile_coords
+pub fn coords_fit(tile_width: f32) -> bool {
+    tile_width >= MIN_COORD_TILE_PX
+}
+
+// Every open passage once, lower grid index first, in grid order
+pub fn adjacency_segments(tiles: &Vec2d<Tile>) -> Vec<((usize, usize), (usize, usize))> {
+    let index = |(col, row): (usize, usize)| row * tiles.cols + col;
+    let mut segments = Vec::new();
+    for tile in &tiles.vec {
+        let pos = (tile.col, tile.row);
+        let mut next: Vec<(usize, usize)> = open_neighbors(tiles, pos)
+            .into_iter()
+            .filter(|&next| index(next) > index(pos))
+            .collect();
+        next.sort_by_key(|&next| index(next));
+        next.dedup();
+        segments.extend(next.into_iter().map(|next| (pos, next)));
+    }
+    segments
+}
+
+// Line between the centers of each connected pair, the passage graph as the
+// movement code sees it. Jumps across the edge of a wrapping maze are left out
+pub fn draw_adjacency(tiles: &Vec2d<Tile>, color: Color) {
+    let center = |(col, row): (usize, usize)| {
+        let tile = tiles.index(col, row);
+        tile.screen_position + vec2(tile.width, tile.height) / 2.0
+    };
+    for (a, b) in adjacency_segments(tiles) {
+        if a.0.abs_diff(b.0) + a.1.abs_diff(b.1) != 1 {
+            continue;
+        }
+        let (from, to) = (center(a), center(b));
+        let thickness = (tiles.index(a.0, a.1).width / 10.0).max(1.0);
+        draw_line(from.x, from.y, to.x, to.y, thickness, color);
+    }
+}
+
+// "col,row" centered in every tile, nothing when tiles are too small to read
+pub fn draw_tile_coords(tiles: &Vec2d<Tile>) {
+    let Some(first) = tiles.vec.first() else {
+        return;
+    };
+    if !coords_fit(first.width) {
+        return;
+    }
+    let font_size = (first.width * 0.3) as u16;
+    for tile in &tiles.vec {
+        let label = format!("{},{}", tile.col, tile.row);
+        let size = measure_text(&label, None, font_size, 1.0);
+        draw_text(
+            &label,
+            tile.screen_position.x + (tile.width - size.width) / 2.0,
+            tile.screen_position.y + (tile.height + size.offset_y) / 2.0,
+            font_size as f32,
+            WHITE,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::layout_tiles_letterboxed;
+    use crate::maze::{blank_tiles, remove_walls_between_positions};
+
+    #[test]
+    fn stack_tiles_skips_the_head_and_repeats() {
+        let stack = [(0, 0), (1, 0), (1, 1), (1, 0), (2, 0)];
+        assert_eq!(stack_tiles(&stack, (2, 0)), vec![(0, 0), (1, 0), (1, 1)]);
+        assert!(stack_tiles(&[(3, 3)], (3, 3)).is_empty());
+    }
+
+    #[test]
+    fn adjacency_segments_list_each_passage_once() {
+        let mut tiles = blank_tiles(2, 2);
+        assert!(adjacency_segments(&tiles).is_empty());
+        remove_walls_between_positions(&mut tiles, (0, 1), (1, 1));
+        remove_walls_between_positions(&mut tiles, (0, 0), (0, 1));
+        remove_walls_between_positions(&mut tiles, (0, 0), (1, 0));
+        assert_eq!(
+            adjacency_segments(&tiles),
+            vec![((0, 0), (1, 0)), ((0, 0), (0, 1)), ((0, 1), (1, 1))]
+        );
+    }
+
+    #[test]
+    fn coords_only_fit_readable_tiles() {
+        assert!(coords_fit(MIN_COORD_TILE_PX));
+        assert!(coords_fit(MIN_COORD_TILE_PX * 2.0));
+        assert!(!coords_fit(MIN_COORD_TILE_PX - 0.5));
+        assert!(!coords_fit(1.0));
+    }
+
+    #[test]
+    fn background_grid_lines_up_with_the_tiles() {
+        // Letterboxed, so the grid starts inside the margins
+        let tiles = layout_tiles_letterboxed(1000.0, 700.0);
+        let first = &tiles.vec[0];
+        let bounds = maze_bounds(&tiles);
+        let columns = grid_lines(first.screen_position.x, first.width, 1000.0);
+        let rows = grid_lines(first.screen_position.y, first.height, 700.0);
+        assert!(columns[0] >= 0.0 && columns[0] < first.width);
+        assert!(rows[0] >= 0.0 && rows[0] < first.height);
+        for tile in &tiles.vec {
+            assert!(columns.contains(&tile.screen_position.x));
+            assert!(rows.contains(&tile.screen_position.y));
+        }
+        assert!(columns.contains(&bounds.right()));
+        assert!(rows.contains(&bounds.bottom()));
+    }
+
+    #[test]
+    fn explored_shading_only_while_carving() {
+        let mut visited: HashSet<(usize, usize)> = [(1, 1), (0, 1), (1, 0)].into_iter().collect();
+        assert_eq!(explored_shading(&visited, 4), vec![(1, 0), (0, 1), (1, 1)]);
+        visited.insert((0, 0));
+        assert!(explored_shading(&visited, 4).is_empty());
+    }
+}
@@ -0,0 +1,77 @@
+use macroquad::prelude::*;
+
+// Seconds a particle lives
+const PARTICLE_LIFE: f32 = 1.0;
+
+#[derive(Debug, Clone)]
+pub struct Particle {
+    pub pos: Vec2,
+    pub velocity: Vec2,
+    // Seconds left, the particle is culled at 0
+    pub life: f32,
+    pub color: Color,
+}
+
+#[derive(Debug, Default)]
+pub struct Particles {
+    pub particles: Vec<Particle>,
+}
+
+impl Particles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Circles flying out evenly in all directions from `pos`
+    pub fn spawn_burst(&mut self, pos: Vec2, count: usize) {
+        const COLORS: [Color; 4] = [YELLOW, GOLD, ORANGE, WHITE];
+        for i in 0..count {
+            let angle = i as f32 / count as f32 * std::f32::consts::TAU;
+            let speed = rand::gen_range(60.0, 160.0);
+            self.particles.push(Particle {
+                pos,
+                velocity: Vec2::from_angle(angle) * speed,
+                life: PARTICLE_LIFE,
+                color: COLORS[i % COLORS.len()],
+            });
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for particle in &mut self.particles {
+            particle.pos += particle.velocity * dt;
+            particle.life -= dt;
+        }
+        self.particles.retain(|particle| particle.life > 0.0);
+    }
+
+    pub fn draw(&self) {
+        for particle in &self.particles {
+            // Grow while fading out
+            let t = particle.life / PARTICLE_LIFE;
+            let mut color = particle.color;
+            color.a = t;
+            draw_circle(particle.pos.x, particle.pos.y, 2.0 + (1.0 - t) * 6.0, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn particles_fade_and_are_culled_when_expired() {
+        let mut particles = Particles::new();
+        particles.spawn_burst(vec2(50.0, 50.0), 8);
+        assert_eq!(particles.particles.len(), 8);
+        particles.update(PARTICLE_LIFE / 2.0);
+        assert_eq!(particles.particles.len(), 8);
+        for particle in &particles.particles {
+            assert!(particle.life < PARTICLE_LIFE && particle.life > 0.0);
+            assert_ne!(particle.pos, vec2(50.0, 50.0));
+        }
+        particles.update(PARTICLE_LIFE / 2.0);
+        assert!(particles.particles.is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+// How long a single peek can last (seconds)
+const PEEK_DURATION: f64 = 1.5;
+
+// Cooldown between peeks, longer for bigger mazes
+pub fn peek_cooldown(cols: usize, rows: usize) -> f64 {
+    (cols * rows) as f64 / 100.0
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Peek {
+    pub peek_until: Option<f64>,
+    pub cooldown_until: f64,
+}
+
+impl Peek {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Advance the peek state machine, returns if the solution should be shown
+    pub fn update(&mut self, held: bool, now: f64, cooldown: f64) -> bool {
+        if now < self.cooldown_until {
+            return false;
+        }
+
+        match (held, self.peek_until) {
+            (true, None) => {
+                self.peek_until = Some(now + PEEK_DURATION);
+                true
+            }
+            (true, Some(until)) if now < until => true,
+            // Held too long or released, either way the peek is over
+            (_, Some(_)) => {
+                self.peek_until = None;
+                self.cooldown_until = now + cooldown;
+                false
+            }
+            (false, None) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peek_shows_while_held_then_cools_down() {
+        let mut peek = Peek::new();
+        assert!(!peek.update(false, 0.0, 5.0));
+        assert!(peek.update(true, 1.0, 5.0));
+        assert!(peek.update(true, 2.0, 5.0));
+        // Released at 2.2, blocked until 7.2 even while held
+        assert!(!peek.update(false, 2.2, 5.0));
+        assert!(!peek.update(true, 3.0, 5.0));
+        assert!(!peek.update(true, 7.1, 5.0));
+        assert!(peek.update(true, 7.2, 5.0));
+    }
+
+    #[test]
+    fn held_peek_ends_after_its_duration() {
+        let mut peek = Peek::new();
+        assert!(peek.update(true, 0.0, 2.0));
+        assert!(peek.update(true, PEEK_DURATION - 0.1, 2.0));
+        assert!(!peek.update(true, PEEK_DURATION, 2.0));
+        assert_eq!(peek.cooldown_until, PEEK_DURATION + 2.0);
+        assert!(!peek.update(true, PEEK_DURATION + 1.0, 2.0));
+    }
+
+    #[test]
+    fn cooldown_grows_with_maze_size() {
+        assert!(peek_cooldown(20, 20) > peek_cooldown(10, 10));
+    }
+}
@@ -1,7 +1,61 @@
+use crate::maze::{are_connected, tile_at_screen};
+use crate::theme::Theme;
 use crate::tile::{Tile, Wall};
 use crate::utils::Vec2d;
 
 use macroquad::prelude::*;
+use std::collections::{HashSet, VecDeque};
+
+pub const PLAYER_TILES_PER_SECOND: f32 = 4.0;
+
+// Seconds before another teleport pad can fire
+const TELEPORT_COOLDOWN: f32 = 0.5;
+
+// How close the player's center must get to the exit's center to win, as a
+// share of the tile size
+pub const EXIT_RADIUS: f32 = 0.25;
+// Inside the exit radius the player is pulled to the exit's center, the win
+// counts once they are this close to it (share of the tile size)
+pub const EXIT_CENTER_TOLERANCE: f32 = 0.02;
+
+// Shortest axis that wraps around in a toroidal maze
+pub const MIN_WRAP_LEN: usize = 3;
+
+pub type Teleport = ((usize, usize), (usize, usize));
+
+// Tile the player's center is on. A center exactly on an edge still counts
+// as `current`, the tile only changes once the edge is really crossed
+pub fn tile_under_center(
+    tiles: &Vec2d<Tile>,
+    center: Vec2,
+    current: (usize, usize),
+) -> Option<(usize, usize)> {
+    let tile = tiles.index(current.0, current.1);
+    let start = tile.screen_position;
+    let end = start + vec2(tile.width, tile.height);
+    if (start.x..=end.x).contains(&center.x) && (start.y..=end.y).contains(&center.y) {
+        return Some(current);
+    }
+    tile_at_screen(tiles, center.x, center.y)
+}
+
+// Every pad of every pair
+pub fn teleport_pads(teleports: &[Teleport]) -> Vec<(usize, usize)> {
+    teleports.iter().flat_map(|&(a, b)| [a, b]).collect()
+}
+
+// The linked pad if `tile` is one end of a teleport pair
+pub fn teleport_target(teleports: &[Teleport], tile: (usize, usize)) -> Option<(usize, usize)> {
+    teleports.iter().find_map(|&(a, b)| {
+        if a == tile {
+            Some(b)
+        } else if b == tile {
+            Some(a)
+        } else {
+            None
+        }
+    })
+}
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Direction {
@@ -12,29 +66,121 @@ pub enum Direction {
     None,
 }
 
+impl Direction {
+    // Adjacent grid position in this direction, None past the maze edge
+    // unless `wrap` carries it around to the opposite side
+    pub fn neighbor(
+        self,
+        pos: (usize, usize),
+        cols: usize,
+        rows: usize,
+        wrap: bool,
+    ) -> Option<(usize, usize)> {
+        let (col, row) = pos;
+        // An axis shorter than 3 would make both sides the same neighbor
+        let wrap_cols = wrap && cols >= MIN_WRAP_LEN;
+        let wrap_rows = wrap && rows >= MIN_WRAP_LEN;
+        match self {
+            Direction::Up if row > 0 => Some((col, row - 1)),
+            Direction::Up if wrap_rows => Some((col, rows - 1)),
+            Direction::Right if col + 1 < cols => Some((col + 1, row)),
+            Direction::Right if wrap_cols => Some((0, row)),
+            Direction::Down if row + 1 < rows => Some((col, row + 1)),
+            Direction::Down if wrap_rows => Some((col, 0)),
+            Direction::Left if col > 0 => Some((col - 1, row)),
+            Direction::Left if wrap_cols => Some((cols - 1, row)),
+            _ => None,
+        }
+    }
+
+    // Side of the tile this direction leaves through
+    pub fn wall(self) -> Option<Wall> {
+        match self {
+            Direction::Up => Some(Wall::Top),
+            Direction::Right => Some(Wall::Right),
+            Direction::Down => Some(Wall::Bottom),
+            Direction::Left => Some(Wall::Left),
+            Direction::None => None,
+        }
+    }
+
+    // Unit screen vector, y grows downward
+    pub fn delta(self) -> Option<Vec2> {
+        match self {
+            Direction::Up => Some(Vec2::new(0.0, -1.0)),
+            Direction::Right => Some(Vec2::new(1.0, 0.0)),
+            Direction::Down => Some(Vec2::new(0.0, 1.0)),
+            Direction::Left => Some(Vec2::new(-1.0, 0.0)),
+            Direction::None => None,
+        }
+    }
+
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Right => Direction::Left,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::None => Direction::None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Player {
     // Grid position
     pub tile_pos: (usize, usize), // (col, row)
     pub screen_pos: Vec2,
-    // Movement speed (pixels per second)
+    // Top movement speed (pixels per second)
     pub speed: f32,
+    // Speed gained or lost per second (pixels per second squared), infinite
+    // starts and stops instantly
+    pub acceleration: f32,
+    pub current_speed: f32,
     pub radius: f32,
     pub color: Color,
     pub current_direction: Direction,
     pub tile_size: f32,
+    // Win distance from the exit's center, share of tile_size
+    pub exit_radius: f32,
+    // Distance from the exit's center the win waits for, share of tile_size
+    pub exit_tolerance: f32,
+    // Moves taken one per tile, each starting from the tile center
+    pub queued: VecDeque<Direction>,
+    // Tile the player started on, traps send them back here
+    pub spawn: (usize, usize),
+    pub trap_hits: u32,
+    teleport_cooldown: f32,
+    // Pad we were warped onto, ignored until we step off it
+    arrived_on: Option<(usize, usize)>,
 }
 
 impl Player {
-    pub fn new(col: usize, row: usize, tile_size: f32, screen_x: f32, screen_y: f32) -> Self {
+    pub fn new(
+        col: usize,
+        row: usize,
+        tile_size: f32,
+        screen_x: f32,
+        screen_y: f32,
+        color: Color,
+    ) -> Self {
         Self {
             tile_pos: (col, row),
             screen_pos: Vec2::new(screen_x, screen_y),
-            speed: tile_size * 4.0, // Move at 4 tiles per second
+            speed: tile_size * PLAYER_TILES_PER_SECOND,
+            acceleration: f32::INFINITY,
+            current_speed: 0.0,
             radius: tile_size * 0.25,
-            color: YELLOW,
+            color,
             current_direction: Direction::None,
             tile_size,
+            exit_radius: EXIT_RADIUS,
+            exit_tolerance: EXIT_CENTER_TOLERANCE,
+            queued: VecDeque::new(),
+            spawn: (col, row),
+            trap_hits: 0,
+            teleport_cooldown: 0.0,
+            arrived_on: None,
         }
     }
 
@@ -47,86 +193,474 @@ impl Player {
         );
     }
 
-    // returns if found exit
-    pub fn update(&mut self, dt: f32, tiles: &Vec2d<Tile>, first_x: f32, first_y: f32) -> bool {
-        if tiles.index(self.tile_pos.0, self.tile_pos.1).exit {
+    // returns if found exit, a frame after the player settled on its center
+    pub fn update(
+        &mut self,
+        dt: f32,
+        tiles: &Vec2d<Tile>,
+        teleports: &[Teleport],
+        traps: &HashSet<(usize, usize)>,
+        first_x: f32,
+        first_y: f32,
+    ) -> bool {
+        if self.centered_on_exit(tiles, first_x, first_y) {
             return true;
         }
+        // Close enough to win, stop and slide onto the center at top speed
+        // first so the last frame shows the player on the exit
+        if self.reached_exit(tiles, first_x, first_y) {
+            self.current_direction = Direction::None;
+            self.current_speed = 0.0;
+            self.queued.clear();
+            let to_center = self.tile_center(first_x, first_y) - self.screen_pos;
+            self.screen_pos += to_center.clamp_length_max(self.speed * dt);
+            if self.centered_on_exit(tiles, first_x, first_y) {
+                self.screen_pos = self.tile_center(first_x, first_y);
+            }
+            return false;
+        }
+
+        self.teleport_cooldown -= dt;
+        if self.arrived_on.is_some_and(|pad| pad != self.tile_pos) {
+            self.arrived_on = None;
+        }
+        // Queued paths are planned without teleports, so walk over the pads
+        if self.teleport_cooldown <= 0.0
+            && self.queued.is_empty()
+            && self.arrived_on.is_none()
+            && let Some(target) = teleport_target(teleports, self.tile_pos)
+        {
+            self.tile_pos = target;
+            self.screen_pos = self.tile_center(first_x, first_y);
+            self.current_direction = Direction::None;
+            self.teleport_cooldown = TELEPORT_COOLDOWN;
+            self.arrived_on = Some(target);
+            return false;
+        }
 
+        // Turn onto the next queued move once the tile center is reached
+        if !self.queued.is_empty() {
+            let to_center = self.tile_center(first_x, first_y) - self.screen_pos;
+            let arrived = match self.current_direction.delta() {
+                Some(delta) => {
+                    to_center.dot(delta) >= 0.0 && to_center.length() <= self.current_speed * dt
+                }
+                None => true,
+            };
+            if arrived {
+                self.screen_pos = self.tile_center(first_x, first_y);
+                self.current_direction = self.queued.pop_front().unwrap();
+            }
+        }
+
+        self.ramp_speed(dt, self.current_direction != Direction::None);
         if self.current_direction == Direction::None {
             // Not moving, make sure we're centered on the tile
-            self.center_on_tile(first_x, first_y);
+            self.center_on_tile(first_x, first_y, dt);
             return false;
         }
 
         // Calculate movement vector based on direction
-        let move_vector = match self.current_direction {
-            Direction::Up => Vec2::new(0.0, -1.0),
-            Direction::Right => Vec2::new(1.0, 0.0),
-            Direction::Down => Vec2::new(0.0, 1.0),
-            Direction::Left => Vec2::new(-1.0, 0.0),
-            Direction::None => Vec2::new(0.0, 0.0),
-        };
+        let move_vector = self.current_direction.delta().unwrap_or(Vec2::ZERO);
 
         // Calculate new position
-        let new_pos = self.screen_pos + move_vector * self.speed * dt;
-
-        // Calculate grid position from screen position (accounting for offset)
-        let grid_col = ((new_pos.x - first_x) / self.tile_size).floor() as usize;
-        let grid_row = ((new_pos.y - first_y) / self.tile_size).floor() as usize;
-
-        // Check for wall collisions
-        let can_move = match self.current_direction {
-            Direction::Up => !tiles
-                .index(self.tile_pos.0, self.tile_pos.1)
-                .walls
-                .contains(&Wall::Top),
-            Direction::Right => !tiles
-                .index(self.tile_pos.0, self.tile_pos.1)
-                .walls
-                .contains(&Wall::Right),
-            Direction::Down => !tiles
-                .index(self.tile_pos.0, self.tile_pos.1)
-                .walls
-                .contains(&Wall::Bottom),
-            Direction::Left => !tiles
-                .index(self.tile_pos.0, self.tile_pos.1)
-                .walls
-                .contains(&Wall::Left),
-            Direction::None => true,
-        };
+        let mut new_pos = self.screen_pos + move_vector * self.current_speed * dt;
+        // Leaving a wrapping maze re-enters from the opposite edge
+        if tiles.wrap {
+            let width = tiles.cols as f32 * self.tile_size;
+            let height = tiles.rows as f32 * self.tile_size;
+            new_pos.x = first_x + (new_pos.x - first_x).rem_euclid(width);
+            new_pos.y = first_y + (new_pos.y - first_y).rem_euclid(height);
+        }
+
+        // Check for wall collisions, queued moves always reach the tile center
+        // where they turn
+        let to_center = self.tile_center(first_x, first_y) - self.screen_pos;
+        let can_move = (!self.queued.is_empty() && to_center.dot(move_vector) > 0.0)
+            || self
+                .current_direction
+                .neighbor(self.tile_pos, tiles.cols, tiles.rows, tiles.wrap)
+                .is_some_and(|next| are_connected(tiles, self.tile_pos, next));
 
         if can_move {
             // Update screen position
             self.screen_pos = new_pos;
 
-            // Update tile position if changed
-            if grid_col != self.tile_pos.0 || grid_row != self.tile_pos.1 {
-                // Make sure the new position is within the maze bounds
-                if grid_col < tiles.cols && grid_row < tiles.rows {
-                    self.tile_pos = (grid_col, grid_row);
+            // Update tile position if changed, past the maze edge it stays put
+            if let Some(grid_pos) = tile_under_center(tiles, new_pos, self.tile_pos)
+                && grid_pos != self.tile_pos
+            {
+                self.tile_pos = grid_pos;
+                if traps.contains(&self.tile_pos) {
+                    self.trap_hits += 1;
+                    self.tile_pos = self.spawn;
+                    self.screen_pos = self.tile_center(first_x, first_y);
+                    self.current_direction = Direction::None;
+                    self.current_speed = 0.0;
+                    self.queued.clear();
                 }
             }
         } else {
             // Can't move in this direction, stop and center on current tile
             self.current_direction = Direction::None;
-            self.center_on_tile(first_x, first_y);
+            self.current_speed = 0.0;
+            self.center_on_tile(first_x, first_y, dt);
         }
         false
     }
 
-    fn center_on_tile(&mut self, first_x: f32, first_y: f32) {
+    // On the exit tile and within exit_radius of its center
+    pub fn reached_exit(&self, tiles: &Vec2d<Tile>, first_x: f32, first_y: f32) -> bool {
+        tiles.index(self.tile_pos.0, self.tile_pos.1).exit
+            && self.screen_pos.distance(self.tile_center(first_x, first_y))
+                <= self.exit_radius * self.tile_size
+    }
+
+    // On the exit tile and within exit_tolerance of its center
+    pub fn centered_on_exit(&self, tiles: &Vec2d<Tile>, first_x: f32, first_y: f32) -> bool {
+        tiles.index(self.tile_pos.0, self.tile_pos.1).exit
+            && self.screen_pos.distance(self.tile_center(first_x, first_y))
+                <= self.exit_tolerance * self.tile_size
+    }
+
+    // Speed up toward `speed` while moving, slow down toward 0 otherwise
+    fn ramp_speed(&mut self, dt: f32, moving: bool) {
+        let target = if moving { self.speed } else { 0.0 };
+        let step = self.acceleration * dt;
+        self.current_speed = if step.is_finite() {
+            target.clamp(self.current_speed - step, self.current_speed + step)
+        } else {
+            target
+        };
+    }
+
+    fn tile_center(&self, first_x: f32, first_y: f32) -> Vec2 {
+        Vec2::new(
+            first_x + (self.tile_pos.0 as f32 * self.tile_size) + (self.tile_size / 2.0),
+            first_y + (self.tile_pos.1 as f32 * self.tile_size) + (self.tile_size / 2.0),
+        )
+    }
+
+    fn center_on_tile(&mut self, first_x: f32, first_y: f32, dt: f32) {
         // Calculate center position of current tile
-        let center_x = first_x + (self.tile_pos.0 as f32 * self.tile_size) + (self.tile_size / 2.0);
-        let center_y = first_y + (self.tile_pos.1 as f32 * self.tile_size) + (self.tile_size / 2.0);
+        let Vec2 {
+            x: center_x,
+            y: center_y,
+        } = self.tile_center(first_x, first_y);
 
         // Smoothly move toward center
-        let dt = get_frame_time();
         self.screen_pos.x = self.screen_pos.x + (center_x - self.screen_pos.x) * 10.0 * dt;
         self.screen_pos.y = self.screen_pos.y + (center_y - self.screen_pos.y) * 10.0 * dt;
     }
 
+    // Manual input, takes over from any queued moves
     pub fn set_direction(&mut self, direction: Direction) {
+        self.queued.clear();
         self.current_direction = direction;
     }
+
+    // Walk these moves in order, one per tile
+    pub fn follow(&mut self, moves: Vec<Direction>) {
+        self.queued = moves.into();
+        // Snap to the current tile first, the moves start from its center
+        self.current_direction = Direction::None;
+    }
+}
+
+// New player centered on a tile, `acceleration` is in tiles per second squared
+pub fn player_on_tile(
+    tiles: &Vec2d<Tile>,
+    pos: (usize, usize),
+    color: Color,
+    acceleration: f32,
+) -> Player {
+    let tile = tiles.index(pos.0, pos.1);
+    let mut player = Player::new(
+        pos.0,
+        pos.1,
+        tile.width,
+        tile.screen_position.x + tile.width / 2.0,
+        tile.screen_position.y + tile.height / 2.0,
+        color,
+    );
+    player.acceleration = acceleration * tile.width;
+    player
+}
+
+// Every player starts on the same tile, each in its own color
+pub fn spawn_players(
+    tiles: &Vec2d<Tile>,
+    pos: (usize, usize),
+    theme: &Theme,
+    count: usize,
+    acceleration: f32,
+) -> Vec<Player> {
+    (0..count)
+        .map(|i| player_on_tile(tiles, pos, theme.player_color(i), acceleration))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{blank_tiles, mark_exit, remove_walls_between_positions};
+    use crate::maze::{entrance_of, mark_entrance};
+
+    // Blank tiles are one pixel wide with the grid starting at (0, 0)
+    fn player_at(col: usize, row: usize) -> Player {
+        Player::new(col, row, 1.0, col as f32 + 0.5, row as f32 + 0.5, WHITE)
+    }
+
+    fn step(player: &mut Player, tiles: &Vec2d<Tile>, dt: f32) -> bool {
+        player.update(dt, tiles, &[], &HashSet::new(), 0.0, 0.0)
+    }
+
+    fn corridor(cols: usize) -> Vec2d<Tile> {
+        let mut tiles = blank_tiles(cols, 1);
+        for col in 1..cols {
+            remove_walls_between_positions(&mut tiles, (col - 1, 0), (col, 0));
+        }
+        tiles
+    }
+
+    #[test]
+    fn walls_stop_the_player() {
+        let tiles = blank_tiles(2, 1);
+        let mut player = player_at(0, 0);
+        player.set_direction(Direction::Right);
+        for _ in 0..30 {
+            step(&mut player, &tiles, 0.05);
+        }
+        assert_eq!(player.tile_pos, (0, 0));
+        assert_eq!(player.current_direction, Direction::None);
+        assert!(player.screen_pos.distance(vec2(0.5, 0.5)) < 0.01);
+    }
+
+    #[test]
+    fn open_walls_let_the_player_through() {
+        let tiles = corridor(3);
+        let mut player = player_at(0, 0);
+        player.set_direction(Direction::Right);
+        for _ in 0..30 {
+            step(&mut player, &tiles, 0.05);
+        }
+        assert_eq!(player.tile_pos, (2, 0));
+    }
+
+    #[test]
+    fn wrapping_edges_carry_the_player_across() {
+        let mut tiles = corridor(3);
+        tiles.wrap = true;
+        remove_walls_between_positions(&mut tiles, (2, 0), (0, 0));
+        let mut player = player_at(0, 0);
+        player.set_direction(Direction::Left);
+        for _ in 0..3 {
+            step(&mut player, &tiles, 0.05);
+        }
+        assert_eq!(player.tile_pos, (2, 0));
+        assert!(player.screen_pos.x > 2.0 && player.screen_pos.x < 3.0);
+    }
+
+    #[test]
+    fn double_time_scale_moves_twice_as_far() {
+        let tiles = corridor(4);
+        let moved = |dt: f32| {
+            let mut player = player_at(0, 0);
+            player.set_direction(Direction::Right);
+            step(&mut player, &tiles, dt);
+            player.screen_pos.x - 0.5
+        };
+        assert!((moved(0.1) - 2.0 * moved(0.05)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn win_waits_until_centered_on_the_exit() {
+        let mut tiles = corridor(3);
+        mark_exit(&mut tiles, (2, 0));
+        let mut player = player_at(0, 0);
+        player.set_direction(Direction::Right);
+        let mut centered_before = false;
+        for _ in 0..120 {
+            let won = step(&mut player, &tiles, 1.0 / 60.0);
+            if won {
+                assert!(centered_before, "won before a centered frame");
+                assert_eq!(player.screen_pos, vec2(2.5, 0.5));
+                return;
+            }
+            centered_before = player.screen_pos == vec2(2.5, 0.5);
+        }
+        panic!("never reached the exit");
+    }
+
+    #[test]
+    fn teleports_fire_once_per_entry() {
+        let tiles = corridor(6);
+        let teleports = [((1, 0), (4, 0))];
+        let mut player = player_at(0, 0);
+        let mut warps = 0;
+        let mut run = |player: &mut Player, direction: Direction, frames: usize| {
+            player.set_direction(direction);
+            for _ in 0..frames {
+                let before = player.tile_pos;
+                player.update(0.05, &tiles, &teleports, &HashSet::new(), 0.0, 0.0);
+                if before.0.abs_diff(player.tile_pos.0) > 1 {
+                    warps += 1;
+                }
+            }
+        };
+        run(&mut player, Direction::Right, 10);
+        assert_eq!(player.tile_pos, (4, 0));
+        // Standing on the far pad doesn't send the player back
+        run(&mut player, Direction::None, 40);
+        assert_eq!(player.tile_pos, (4, 0));
+        // Stepping off and back on is a new entry
+        run(&mut player, Direction::Right, 20);
+        assert_eq!(player.tile_pos, (5, 0));
+        run(&mut player, Direction::Left, 20);
+        assert_eq!(player.tile_pos, (1, 0));
+        assert_eq!(warps, 2);
+    }
+
+    #[test]
+    fn traps_send_the_player_back_to_the_spawn() {
+        let tiles = corridor(5);
+        let traps = HashSet::from([(2, 0)]);
+        let mut player = player_at(0, 0);
+        player.set_direction(Direction::Right);
+        for _ in 0..10 {
+            player.update(0.05, &tiles, &[], &traps, 0.0, 0.0);
+        }
+        assert_eq!(player.tile_pos, (0, 0));
+        assert_eq!(player.screen_pos, vec2(0.5, 0.5));
+        assert_eq!(player.trap_hits, 1);
+        assert_eq!(player.current_direction, Direction::None);
+    }
+
+    #[test]
+    fn neighbors_wrap_around_a_wrapping_grid() {
+        let mut tiles = blank_tiles(4, 3);
+        tiles.wrap = true;
+        let neighbor = |direction: Direction, pos, tiles: &Vec2d<Tile>| {
+            direction.neighbor(pos, tiles.cols, tiles.rows, tiles.wrap)
+        };
+        assert_eq!(neighbor(Direction::Up, (1, 0), &tiles), Some((1, 2)));
+        assert_eq!(neighbor(Direction::Right, (3, 1), &tiles), Some((0, 1)));
+        assert_eq!(neighbor(Direction::Down, (2, 2), &tiles), Some((2, 0)));
+        assert_eq!(neighbor(Direction::Left, (0, 2), &tiles), Some((3, 2)));
+        assert_eq!(neighbor(Direction::Left, (2, 1), &tiles), Some((1, 1)));
+        assert_eq!(neighbor(Direction::None, (0, 0), &tiles), None);
+
+        // The edge walls between wrapped neighbors come down like inner ones
+        remove_walls_between_positions(&mut tiles, (3, 1), (0, 1));
+        assert!(!tiles.index(3, 1).walls().contains(&Wall::Right));
+        assert!(!tiles.index(0, 1).walls().contains(&Wall::Left));
+        assert!(are_connected(&tiles, (0, 1), (3, 1)));
+
+        // Axes under MIN_WRAP_LEN and unwrapped grids stop at the edge
+        let narrow = Vec2d {
+            wrap: true,
+            ..blank_tiles(2, 3)
+        };
+        assert_eq!(neighbor(Direction::Right, (1, 0), &narrow), None);
+        assert_eq!(neighbor(Direction::Up, (1, 0), &narrow), Some((1, 2)));
+        tiles.wrap = false;
+        assert_eq!(neighbor(Direction::Up, (1, 0), &tiles), None);
+    }
+
+    #[test]
+    fn tile_under_center_changes_only_past_the_edge() {
+        let tiles = blank_tiles(3, 2);
+        assert_eq!(
+            tile_under_center(&tiles, vec2(0.5, 0.5), (0, 0)),
+            Some((0, 0))
+        );
+        // A center right on the shared edge still belongs to the current tile
+        assert_eq!(
+            tile_under_center(&tiles, vec2(1.0, 0.5), (0, 0)),
+            Some((0, 0))
+        );
+        assert_eq!(
+            tile_under_center(&tiles, vec2(1.0, 0.5), (1, 0)),
+            Some((1, 0))
+        );
+        assert_eq!(
+            tile_under_center(&tiles, vec2(1.01, 0.5), (0, 0)),
+            Some((1, 0))
+        );
+        assert_eq!(
+            tile_under_center(&tiles, vec2(2.5, 1.5), (0, 0)),
+            Some((2, 1))
+        );
+        assert_eq!(tile_under_center(&tiles, vec2(3.5, 0.5), (2, 0)), None);
+    }
+
+    #[test]
+    fn acceleration_ramps_speed_up_and_down() {
+        let tiles = corridor(20);
+        let mut player = player_at(0, 0);
+        player.acceleration = 2.0;
+        player.set_direction(Direction::Right);
+        let mut last = 0.0;
+        for _ in 0..5 {
+            step(&mut player, &tiles, 0.1);
+            assert!(player.current_speed > last);
+            last = player.current_speed;
+        }
+        assert!((last - 1.0).abs() < 1e-5);
+        // Never past the top speed
+        for _ in 0..20 {
+            step(&mut player, &tiles, 0.1);
+        }
+        assert_eq!(player.current_speed, player.speed);
+        player.set_direction(Direction::None);
+        step(&mut player, &tiles, 0.1);
+        assert!(player.current_speed < player.speed && player.current_speed > 0.0);
+
+        // The default starts and stops at once
+        let mut instant = player_at(0, 0);
+        instant.set_direction(Direction::Right);
+        step(&mut instant, &tiles, 0.01);
+        assert_eq!(instant.current_speed, instant.speed);
+        instant.set_direction(Direction::None);
+        step(&mut instant, &tiles, 0.01);
+        assert_eq!(instant.current_speed, 0.0);
+    }
+
+    #[test]
+    fn exit_counts_only_inside_the_radius() {
+        let mut tiles = blank_tiles(1, 1);
+        mark_exit(&mut tiles, (0, 0));
+        let mut player = player_at(0, 0);
+        player.tile_size = 10.0;
+        player.exit_radius = 0.25;
+        let center = player.tile_center(0.0, 0.0);
+        player.screen_pos = center + Vec2::new(2.4, 0.0);
+        assert!(player.reached_exit(&tiles, 0.0, 0.0));
+        player.screen_pos = center + Vec2::new(2.6, 0.0);
+        assert!(!player.reached_exit(&tiles, 0.0, 0.0));
+        // Not on an exit tile, however close
+        tiles.index_mut(0, 0).exit = false;
+        player.screen_pos = center;
+        assert!(!player.reached_exit(&tiles, 0.0, 0.0));
+    }
+
+    #[test]
+    fn players_spawn_on_the_only_entrance() {
+        let mut tiles = blank_tiles(4, 3);
+        mark_entrance(&mut tiles, (0, 0));
+        mark_entrance(&mut tiles, (2, 2));
+        let marked: Vec<&Tile> = tiles.vec.iter().filter(|tile| tile.entrance).collect();
+        assert_eq!(marked.len(), 1);
+        // Moving the entrance closes the old opening
+        assert_eq!(tiles.index(0, 0).wall_mask(), 0xf);
+        let entrance = entrance_of(&tiles).unwrap();
+        assert_eq!(entrance, (2, 2));
+        let players = spawn_players(&tiles, entrance, &Theme::default(), 2, 1.0);
+        assert_eq!(players.len(), 2);
+        for player in &players {
+            assert_eq!(player.tile_pos, (2, 2));
+            assert_eq!(player.screen_pos, vec2(2.5, 2.5));
+        }
+        assert_ne!(players[0].color, players[1].color);
+    }
 }
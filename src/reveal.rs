@@ -0,0 +1,85 @@
+use crate::solver::compute_distances;
+use crate::tile::Tile;
+use crate::utils::Vec2d;
+
+use macroquad::prelude::*;
+
+// Wavefront speed in tiles per second
+const WAVE_SPEED: f32 = 20.0;
+// Wavefront thickness in tiles
+const WAVE_WIDTH: f32 = 3.0;
+
+// Whether a tile at `dist` is inside the wavefront of the given radius
+pub fn wavefront_active(dist: usize, radius: f32, width: f32) -> bool {
+    let dist = dist as f32;
+    dist <= radius && radius - dist < width
+}
+
+// Win animation: waves of light spreading out from the exit
+pub struct FloodReveal {
+    distances: Vec2d<Option<usize>>,
+    radius: f32,
+    max_dist: usize,
+}
+
+impl FloodReveal {
+    pub fn new(tiles: &Vec2d<Tile>, exit: (usize, usize)) -> Self {
+        let distances = compute_distances(tiles, exit);
+        let max_dist = distances.vec.iter().flatten().copied().max().unwrap_or(0);
+        Self {
+            distances,
+            radius: 0.0,
+            max_dist,
+        }
+    }
+
+    // Returns true once the wave has passed the farthest tile
+    pub fn update(&mut self, dt: f32) -> bool {
+        self.radius += WAVE_SPEED * dt;
+        self.radius > self.max_dist as f32 + WAVE_WIDTH
+    }
+
+    pub fn draw(&self, tiles: &Vec2d<Tile>) {
+        for (tile, dist) in tiles.vec.iter().zip(&self.distances.vec) {
+            let Some(dist) = *dist else { continue };
+            if wavefront_active(dist, self.radius, WAVE_WIDTH) {
+                // Brightest at the leading edge of the wave
+                let alpha = 1.0 - (self.radius - dist as f32) / WAVE_WIDTH;
+                draw_rectangle(
+                    tile.screen_position.x,
+                    tile.screen_position.y,
+                    tile.width,
+                    tile.height,
+                    Color::new(1.0, 1.0, 0.6, alpha * 0.7),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{blank_tiles, remove_walls_between_positions};
+
+    #[test]
+    fn wavefront_covers_a_band_behind_the_radius() {
+        // Radius 5 and width 3 light up distances 3, 4 and 5
+        let lit: Vec<usize> = (0..10).filter(|&d| wavefront_active(d, 5.0, 3.0)).collect();
+        assert_eq!(lit, vec![3, 4, 5]);
+        assert!(!wavefront_active(1, 0.5, 3.0));
+        assert!(wavefront_active(0, 0.0, 3.0));
+    }
+
+    #[test]
+    fn reveal_finishes_once_past_the_farthest_tile() {
+        let mut tiles = blank_tiles(3, 1);
+        remove_walls_between_positions(&mut tiles, (0, 0), (1, 0));
+        remove_walls_between_positions(&mut tiles, (1, 0), (2, 0));
+        let mut reveal = FloodReveal::new(&tiles, (0, 0));
+        assert!(!reveal.update(0.1));
+        // Farthest tile is 2 away, the wave ends past 2 + WAVE_WIDTH
+        assert!(reveal.update((2.0 + WAVE_WIDTH) / WAVE_SPEED));
+        assert!(!wavefront_active(2, reveal.radius, WAVE_WIDTH));
+    }
+}
@@ -0,0 +1,119 @@
+use crate::daily::utc_date;
+
+use macroquad::prelude::*;
+use std::io;
+use std::path::Path;
+
+// maze_<date>_<time>.png for a screenshot taken at `unix_secs`, in UTC
+pub fn screenshot_name(unix_secs: f64) -> String {
+    let (year, month, day) = utc_date(unix_secs);
+    let seconds = unix_secs.max(0.0) as u64 % 86_400;
+    format!(
+        "maze_{:04}-{:02}-{:02}_{:02}-{:02}-{:02}.png",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+// The frame with every pixel made opaque, some backends leave the alpha of
+// the back buffer at 0 and the PNG would come out see-through
+pub fn opaque(image: &Image) -> Image {
+    let mut bytes = image.bytes.clone();
+    for pixel in bytes.chunks_exact_mut(4) {
+        pixel[3] = 255;
+    }
+    Image {
+        bytes,
+        width: image.width,
+        height: image.height,
+    }
+}
+
+// The image upside down, turns rows drawn top down into the order write_png
+// expects
+pub fn flip_rows(image: &Image) -> Image {
+    let row_len = image.width as usize * 4;
+    Image {
+        bytes: image
+            .bytes
+            .chunks(row_len)
+            .rev()
+            .flatten()
+            .copied()
+            .collect(),
+        width: image.width,
+        height: image.height,
+    }
+}
+
+// Write `image`, in the bottom up row order get_screen_data gives, as a PNG.
+// export_png panics on a failed write, so the file is created first to
+// report a missing directory or permissions as an error instead
+pub fn write_png(image: &Image, path: &Path) -> io::Result<()> {
+    let name = path
+        .to_str()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path isn't UTF-8"))?;
+    std::fs::File::create(path)?;
+    image.export_png(name);
+    Ok(())
+}
+
+// Capture what is on screen right now, players and overlays included
+pub fn save_screenshot(path: &Path) -> io::Result<()> {
+    write_png(&opaque(&get_screen_data()), path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2 x 2 frame in get_screen_data's order, bottom row first, alpha left at 0
+    fn frame() -> Image {
+        Image {
+            bytes: vec![
+                255, 0, 0, 0, 0, 255, 0, 0, // bottom: red, green
+                0, 0, 255, 0, 255, 255, 255, 0, // top: blue, white
+            ],
+            width: 2,
+            height: 2,
+        }
+    }
+
+    #[test]
+    fn screenshot_names_carry_the_utc_time() {
+        assert_eq!(screenshot_name(0.0), "maze_1970-01-01_00-00-00.png");
+        // 2024-02-29 13:05:09
+        assert_eq!(
+            screenshot_name(1_709_164_800.0 + 13.0 * 3600.0 + 5.0 * 60.0 + 9.5),
+            "maze_2024-02-29_13-05-09.png"
+        );
+    }
+
+    #[test]
+    fn frames_are_written_opaque_and_top_down() {
+        let image = opaque(&frame());
+        assert!(image.bytes.chunks(4).all(|pixel| pixel[3] == 255));
+        assert_eq!(flip_rows(&flip_rows(&image)).bytes, image.bytes);
+
+        let path = std::env::temp_dir().join(format!("maze_screenshot_{}.png", std::process::id()));
+        write_png(&image, &path).unwrap();
+        let written = Image::from_file_with_format(&std::fs::read(&path).unwrap(), None).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((written.width, written.height), (2, 2));
+        // The top row comes first in the file
+        assert_eq!(written.bytes, flip_rows(&image).bytes);
+        assert_eq!(&written.bytes[..4], &[0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn missing_directory_is_an_error() {
+        let path = std::env::temp_dir()
+            .join("maze_no_such_dir")
+            .join("shot.png");
+        assert!(write_png(&opaque(&frame()), &path).is_err());
+    }
+}
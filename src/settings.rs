@@ -0,0 +1,84 @@
+use crate::maze::{MIN_SOLUTION_LEN, NUMBER_OF_TILES_IN_BIGGER_AXIS};
+
+use macroquad::prelude::*;
+
+// Tuning numbers for generation and the on screen controls, built once at
+// startup and handed to whatever needs them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    // Backtracker steps carved per animation tick
+    pub carve_steps: usize,
+    // Seconds between animation ticks
+    pub carve_interval: f64,
+    // Milliseconds of carving per frame with `--instant`
+    pub generation_budget_ms: f64,
+    // Range the share of walls opened into loops is picked from without `--loops`
+    pub loop_share: (f32, f32),
+    // Control pad side as a share of the screen height
+    pub control_pad_share: f32,
+    // Pixels between the control pad and the bottom right corner
+    pub control_pad_margin: f32,
+    // Keep the carved maze as it is, no loops and exactly one solution
+    pub perfect: bool,
+    // Put the exit on the border, as far from the entrance as it gets
+    pub border_exit: bool,
+    // Start the players on a random tile instead of where the last maze ended
+    pub random_spawn: bool,
+    // Steps from the entrance a random exit must be, the farthest tile is
+    // used when no tile is that far
+    pub min_solution_len: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            carve_steps: (NUMBER_OF_TILES_IN_BIGGER_AXIS / 10) as usize,
+            carve_interval: 0.1 / NUMBER_OF_TILES_IN_BIGGER_AXIS as f64,
+            generation_budget_ms: 8.0,
+            loop_share: (0.01, 0.05),
+            control_pad_share: 0.25,
+            control_pad_margin: 20.0,
+            perfect: false,
+            border_exit: false,
+            random_spawn: false,
+            min_solution_len: MIN_SOLUTION_LEN,
+        }
+    }
+}
+
+impl Settings {
+    // Square the control pad fills in the bottom right corner of the screen
+    pub fn control_pad_rect(&self, screen_w: f32, screen_h: f32) -> Rect {
+        let size = screen_h * self.control_pad_share;
+        Rect::new(
+            screen_w - size - self.control_pad_margin,
+            screen_h - size - self.control_pad_margin,
+            size,
+            size,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_match_the_old_constants() {
+        let settings = Settings::default();
+        assert_eq!(settings.carve_steps, 3);
+        assert_eq!(settings.carve_interval, 0.1 / 30.0);
+        assert_eq!(settings.generation_budget_ms, 8.0);
+        assert_eq!(settings.loop_share, (0.01, 0.05));
+        assert_eq!(settings.control_pad_share, 0.25);
+        assert_eq!(settings.control_pad_margin, 20.0);
+        assert_eq!(settings.min_solution_len, 30);
+        assert!(!settings.perfect && !settings.border_exit && !settings.random_spawn);
+    }
+
+    #[test]
+    fn control_pad_sits_in_the_bottom_right_corner() {
+        let rect = Settings::default().control_pad_rect(800.0, 600.0);
+        assert_eq!(rect, Rect::new(630.0, 430.0, 150.0, 150.0));
+    }
+}
@@ -0,0 +1,103 @@
+use std::fmt;
+
+// (start, exit)
+pub type StartExit = ((usize, usize), (usize, usize));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupError {
+    MissingStart,
+    MissingExit,
+    OutOfBounds((usize, usize)),
+    SameTile,
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::MissingStart => write!(f, "pick a start tile"),
+            SetupError::MissingExit => write!(f, "pick an exit tile"),
+            SetupError::OutOfBounds((col, row)) => {
+                write!(f, "tile ({}, {}) is outside the maze", col, row)
+            }
+            SetupError::SameTile => write!(f, "start and exit must be different tiles"),
+        }
+    }
+}
+
+impl std::error::Error for SetupError {}
+
+// Custom start/exit picked by clicking tiles, clicks alternate between the two
+#[derive(Debug, Clone, Default)]
+pub struct Setup {
+    pub start: Option<(usize, usize)>,
+    pub exit: Option<(usize, usize)>,
+    picking_exit: bool,
+}
+
+impl Setup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn click(&mut self, tile: (usize, usize)) {
+        if self.picking_exit {
+            self.exit = Some(tile);
+        } else {
+            self.start = Some(tile);
+        }
+        self.picking_exit = !self.picking_exit;
+    }
+
+    pub fn picking_exit(&self) -> bool {
+        self.picking_exit
+    }
+
+    // The chosen (start, exit) if they make a playable pair
+    pub fn validate(&self, cols: usize, rows: usize) -> Result<StartExit, SetupError> {
+        let start = self.start.ok_or(SetupError::MissingStart)?;
+        let exit = self.exit.ok_or(SetupError::MissingExit)?;
+        for pos in [start, exit] {
+            if pos.0 >= cols || pos.1 >= rows {
+                return Err(SetupError::OutOfBounds(pos));
+            }
+        }
+        if start == exit {
+            return Err(SetupError::SameTile);
+        }
+        Ok((start, exit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{blank_tiles, tile_at_screen};
+
+    #[test]
+    fn clicks_pick_the_start_then_the_exit() {
+        // 1px tiles from (0, 0), the click lands on the tile under it
+        let tiles = blank_tiles(4, 3);
+        let mut setup = Setup::new();
+        setup.click(tile_at_screen(&tiles, 1.5, 2.2).unwrap());
+        assert!(setup.picking_exit());
+        setup.click(tile_at_screen(&tiles, 3.9, 0.0).unwrap());
+        assert!(!setup.picking_exit());
+        assert_eq!(setup.validate(4, 3), Ok(((1, 2), (3, 0))));
+        // The next click moves the start again
+        setup.click((0, 0));
+        assert_eq!(setup.start, Some((0, 0)));
+    }
+
+    #[test]
+    fn invalid_picks_are_refused() {
+        let mut setup = Setup::new();
+        assert_eq!(setup.validate(4, 4), Err(SetupError::MissingStart));
+        setup.click((1, 1));
+        assert_eq!(setup.validate(4, 4), Err(SetupError::MissingExit));
+        setup.click((1, 1));
+        assert_eq!(setup.validate(4, 4), Err(SetupError::SameTile));
+        setup.click((4, 0));
+        setup.click((2, 2));
+        assert_eq!(setup.validate(4, 4), Err(SetupError::OutOfBounds((4, 0))));
+    }
+}
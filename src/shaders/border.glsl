@@ -9,37 +9,49 @@ uniform float pixels;
 uniform int border_side;
 uniform vec4 tile_color;
 uniform vec4 border_color;
+// Edge softness in border widths, 0 keeps hard pixel edges
+uniform float smoothness;
+// Scene levels, 1.0 is neutral. Contrast pivots around mid gray
+uniform float brightness;
+uniform float contrast;
+
+// How much of the border covers a point `d` (in uv) from a walled side
+float border_cover(float d) {
+    float width = 1.0 / pixels;
+    if (smoothness <= 0.0) {
+        return d < width ? 1.0 : 0.0;
+    }
+    float soft = smoothness * width;
+    return 1.0 - smoothstep(width - soft, width + soft, d);
+}
 
 void main() {
-    vec2 tile_size = vec2(pixels, pixels);
-    
-    // Calculate pixel position within the tile (0-15 for each axis)
-    vec2 tile_pixel = floor(uv * tile_size);
-    
-    // Default color is the tile color
-    vec4 color = tile_color;
-   
+    // Strongest border coverage over the walled sides
+    float cover = 0.0;
+
     // Apply border based on the border_side parameter
     int temp = border_side;
-    if (mod(float(temp), 2.0) == 1.0 && tile_pixel.x == 0.0) {
+    if (mod(float(temp), 2.0) == 1.0) {
         // Left border
-        color = border_color;
+        cover = max(cover, border_cover(uv.x));
     }
     temp = (temp / 2);
-    if (mod(float(temp), 2.0) == 1.0 && tile_pixel.y == 0.0) {
+    if (mod(float(temp), 2.0) == 1.0) {
         // Top border
-        color = border_color;
+        cover = max(cover, border_cover(uv.y));
     }
     temp = (temp / 2);
-    if (mod(float(temp), 2.0) == 1.0 && tile_pixel.x == (pixels - 1.0)) {
+    if (mod(float(temp), 2.0) == 1.0) {
         // Right border
-        color = border_color;
+        cover = max(cover, border_cover(1.0 - uv.x));
     }
     temp = (temp / 2);
-    if (mod(float(temp), 2.0) == 1.0 && tile_pixel.y == (pixels -1.0)) {
+    if (mod(float(temp), 2.0) == 1.0) {
         // Bottom border
-        color = border_color;
+        cover = max(cover, border_cover(1.0 - uv.y));
     }
 
-    gl_FragColor = color;
+    vec4 color = mix(tile_color, border_color, cover);
+    vec3 leveled = ((color.rgb - 0.5) * contrast + 0.5) * brightness;
+    gl_FragColor = vec4(clamp(leveled, 0.0, 1.0), color.a);
 }
@@ -0,0 +1,691 @@
+use crate::maze::{are_connected, entrance_of};
+use crate::player::{Direction, MIN_WRAP_LEN};
+use crate::tile::Tile;
+use crate::utils::Vec2d;
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayError {
+    NoExit,
+    // Walls cut the entrance off from every exit
+    Unreachable {
+        entrance: (usize, usize),
+        exit: (usize, usize),
+    },
+}
+
+impl fmt::Display for PlayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlayError::NoExit => write!(f, "the maze has no exit"),
+            PlayError::Unreachable { entrance, exit } => write!(
+                f,
+                "no path from the entrance {:?} to the exit {:?}",
+                entrance, exit
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PlayError {}
+
+// Tiles reachable in one step from `pos` (adjacent and not separated by a wall)
+pub fn open_neighbors(tiles: &Vec2d<Tile>, pos: (usize, usize)) -> Vec<(usize, usize)> {
+    [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ]
+    .into_iter()
+    .filter_map(|direction| direction.neighbor(pos, tiles.cols, tiles.rows, tiles.wrap))
+    .filter(|&next| are_connected(tiles, pos, next))
+    .collect()
+}
+
+// A maze can be played when some exit is reachable from the entrance, or from
+// the top left tile when no entrance is marked since players spawn there
+pub fn validate_playable(tiles: &Vec2d<Tile>) -> Result<(), PlayError> {
+    let entrance = entrance_of(tiles).unwrap_or((0, 0));
+    let exits: Vec<(usize, usize)> = tiles
+        .vec
+        .iter()
+        .filter(|tile| tile.exit)
+        .map(|tile| (tile.col, tile.row))
+        .collect();
+    let Some(&exit) = exits.first() else {
+        return Err(PlayError::NoExit);
+    };
+    let distances = compute_distances(tiles, entrance);
+    if exits
+        .iter()
+        .any(|&(col, row)| distances.index(col, row).is_some())
+    {
+        Ok(())
+    } else {
+        Err(PlayError::Unreachable { entrance, exit })
+    }
+}
+
+// Every tile can be reached from every other
+pub fn is_fully_connected(tiles: &Vec2d<Tile>) -> bool {
+    compute_distances(tiles, (0, 0))
+        .vec
+        .iter()
+        .all(|distance| distance.is_some())
+}
+
+// BFS step count from `from` to every tile, None for unreachable tiles
+pub fn compute_distances(tiles: &Vec2d<Tile>, from: (usize, usize)) -> Vec2d<Option<usize>> {
+    let mut distances = Vec2d::new(vec![None; tiles.vec.len()], tiles.rows, tiles.cols);
+    let mut queue = VecDeque::new();
+
+    *distances.index_mut(from.0, from.1) = Some(0);
+    queue.push_back(from);
+
+    while let Some(current) = queue.pop_front() {
+        let dist = distances.index(current.0, current.1).unwrap();
+        for (col, row) in open_neighbors(tiles, current) {
+            let next = distances.index_mut(col, row);
+            if next.is_none() {
+                *next = Some(dist + 1);
+                queue.push_back((col, row));
+            }
+        }
+    }
+
+    distances
+}
+
+// Shortest path (both ends included) to the closest reachable tile not in
+// `explored`, the exit never counts so the hint can't give it away
+pub fn path_to_unexplored(
+    tiles: &Vec2d<Tile>,
+    from: (usize, usize),
+    explored: &HashSet<(usize, usize)>,
+) -> Option<Vec<(usize, usize)>> {
+    let mut came_from: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    let mut seen = HashSet::from([from]);
+
+    while let Some(current) = queue.pop_front() {
+        let tile = tiles.index(current.0, current.1);
+        if current != from && !tile.exit && !explored.contains(&current) {
+            let mut path = vec![current];
+            while let Some(&prev) = came_from.get(path.last().unwrap()) {
+                path.push(prev);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for next in open_neighbors(tiles, current) {
+            if seen.insert(next) {
+                came_from.insert(next, current);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+// Reachable tile with the largest distance
+pub fn farthest_tile(distances: &Vec2d<Option<usize>>) -> (usize, usize) {
+    let mut farthest = 0;
+    let mut max = None;
+    for (i, dist) in distances.vec.iter().enumerate() {
+        if let Some(d) = *dist
+            && max.is_none_or(|m| d > m)
+        {
+            max = Some(d);
+            farthest = i;
+        }
+    }
+    distances.coords(farthest)
+}
+
+// Longest shortest path (the tree diameter on a perfect maze), found with a BFS
+// to the farthest tile from any tile and another from there
+pub fn longest_path(tiles: &Vec2d<Tile>) -> Vec<(usize, usize)> {
+    let one_end = farthest_tile(&compute_distances(tiles, (0, 0)));
+    let other_end = farthest_tile(&compute_distances(tiles, one_end));
+    solve_astar(tiles, one_end, other_end).unwrap_or_default()
+}
+
+// Number of distinct shortest paths from start to exit (0 if unreachable),
+// counts add up layer by layer of the BFS and saturate at u64::MAX
+pub fn count_shortest_paths(
+    tiles: &Vec2d<Tile>,
+    start: (usize, usize),
+    exit: (usize, usize),
+) -> u64 {
+    let mut distances: Vec2d<Option<usize>> =
+        Vec2d::new(vec![None; tiles.vec.len()], tiles.rows, tiles.cols);
+    let mut counts = Vec2d::new(vec![0u64; tiles.vec.len()], tiles.rows, tiles.cols);
+    let mut queue = VecDeque::new();
+
+    *distances.index_mut(start.0, start.1) = Some(0);
+    *counts.index_mut(start.0, start.1) = 1;
+    queue.push_back(start);
+
+    while let Some(current) = queue.pop_front() {
+        if current == exit {
+            break;
+        }
+        let dist = distances.index(current.0, current.1).unwrap();
+        let count = *counts.index(current.0, current.1);
+        for (col, row) in open_neighbors(tiles, current) {
+            match *distances.index(col, row) {
+                None => {
+                    *distances.index_mut(col, row) = Some(dist + 1);
+                    *counts.index_mut(col, row) = count;
+                    queue.push_back((col, row));
+                }
+                Some(d) if d == dist + 1 => {
+                    let next = counts.index_mut(col, row);
+                    *next = next.saturating_add(count);
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    *counts.index(exit.0, exit.1)
+}
+
+// Grid distance ignoring walls, going around the edge when the maze wraps
+pub fn manhattan(tiles: &Vec2d<Tile>, a: (usize, usize), b: (usize, usize)) -> usize {
+    let axis = |from: usize, to: usize, len: usize| {
+        let direct = from.abs_diff(to);
+        if tiles.wrap && len >= MIN_WRAP_LEN {
+            direct.min(len - direct)
+        } else {
+            direct
+        }
+    };
+    axis(a.0, b.0, tiles.cols) + axis(a.1, b.1, tiles.rows)
+}
+
+// Cardinal move between each pair of consecutive tiles of a path, stops at
+// the first pair that isn't adjacent
+pub fn path_directions(tiles: &Vec2d<Tile>, path: &[(usize, usize)]) -> Vec<Direction> {
+    path.windows(2)
+        .map_while(|step| {
+            [
+                Direction::Up,
+                Direction::Right,
+                Direction::Down,
+                Direction::Left,
+            ]
+            .into_iter()
+            .find(|direction| {
+                direction.neighbor(step[0], tiles.cols, tiles.rows, tiles.wrap) == Some(step[1])
+            })
+        })
+        .collect()
+}
+
+// A* shortest path from start to goal, both ends included. Counts steps and
+// ignores tile weights, so the manhattan estimate stays admissible
+pub fn solve_astar(
+    tiles: &Vec2d<Tile>,
+    start: (usize, usize),
+    goal: (usize, usize),
+) -> Option<Vec<(usize, usize)>> {
+    let mut solver = SolverState::new(tiles, start, goal);
+    while solver.step(tiles) {}
+    solver.path
+}
+
+// Faint guide of practice mode, the shortest path from `from` to `exit` or
+// nothing when the exit can't be reached
+pub fn practice_guide(
+    tiles: &Vec2d<Tile>,
+    from: (usize, usize),
+    exit: (usize, usize),
+) -> Vec<(usize, usize)> {
+    solve_astar(tiles, from, exit).unwrap_or_default()
+}
+
+// Length of the shortest route from `from` to `exit`, without the route
+pub fn steps_to_go(
+    tiles: &Vec2d<Tile>,
+    from: (usize, usize),
+    exit: (usize, usize),
+) -> Option<usize> {
+    solve_astar(tiles, from, exit).map(|path| path.len() - 1)
+}
+
+// Cheapest path from start to goal by the summed weight of the tiles stepped
+// onto (the start is free), both ends included. Negative weights count as 0.
+// On a maze of equal weights it is as long as the A* path
+pub fn solve_dijkstra(
+    tiles: &Vec2d<Tile>,
+    start: (usize, usize),
+    goal: (usize, usize),
+) -> Option<Vec<(usize, usize)>> {
+    let mut open = BinaryHeap::from([Reverse((Weight(0.0), start))]);
+    let mut cost = HashMap::from([(start, 0.0)]);
+    let mut came_from = HashMap::new();
+    while let Some(Reverse((Weight(g), current))) = open.pop() {
+        // Skip stale heap entries
+        if g > cost[&current] {
+            continue;
+        }
+        if current == goal {
+            let mut path = vec![current];
+            while let Some(&prev) = came_from.get(path.last().unwrap()) {
+                path.push(prev);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for next in open_neighbors(tiles, current) {
+            let next_cost = g + tiles.index(next.0, next.1).weight.max(0.0);
+            if cost.get(&next).is_none_or(|&c| next_cost < c) {
+                cost.insert(next, next_cost);
+                came_from.insert(next, current);
+                open.push(Reverse((Weight(next_cost), next)));
+            }
+        }
+    }
+    None
+}
+
+// Summed weight of the tiles a path steps onto, its start excluded
+pub fn path_weight(tiles: &Vec2d<Tile>, path: &[(usize, usize)]) -> f32 {
+    path.iter()
+        .skip(1)
+        .map(|&(col, row)| tiles.index(col, row).weight.max(0.0))
+        .sum()
+}
+
+// f32 path cost the heap can order
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Weight(f32);
+
+impl Eq for Weight {}
+
+impl PartialOrd for Weight {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Weight {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+// Optimal entrance to exit step count, solved once and kept until the maze's
+// revision (bumped by every wall change), the entrance or the exit change
+#[derive(Debug, Clone, Default)]
+pub struct SolutionCache {
+    // Revision and (entrance, exit) the length was solved for, None until the
+    // first solve
+    revision: Option<u64>,
+    ends: Option<((usize, usize), (usize, usize))>,
+    len: Option<usize>,
+}
+
+impl SolutionCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // None when the exit can't be reached
+    pub fn solution_len(
+        &mut self,
+        tiles: &Vec2d<Tile>,
+        entrance: (usize, usize),
+        exit: (usize, usize),
+    ) -> Option<usize> {
+        if self.revision != Some(tiles.revision) || self.ends != Some((entrance, exit)) {
+            self.len = steps_to_go(tiles, entrance, exit);
+            self.revision = Some(tiles.revision);
+            self.ends = Some((entrance, exit));
+        }
+        self.len
+    }
+}
+
+// (estimate, cost so far, tile)
+type OpenEntry = (usize, usize, (usize, usize));
+
+// A* search run one expansion at a time, so it can be watched
+#[derive(Debug, Clone)]
+pub struct SolverState {
+    pub start: (usize, usize),
+    pub goal: (usize, usize),
+    // Tiles already expanded
+    pub closed: HashSet<(usize, usize)>,
+    // Set once the goal is expanded, both ends included
+    pub path: Option<Vec<(usize, usize)>>,
+    pub done: bool,
+    open: BinaryHeap<Reverse<OpenEntry>>,
+    came_from: HashMap<(usize, usize), (usize, usize)>,
+    cost: HashMap<(usize, usize), usize>,
+}
+
+impl SolverState {
+    pub fn new(tiles: &Vec2d<Tile>, start: (usize, usize), goal: (usize, usize)) -> Self {
+        Self {
+            start,
+            goal,
+            closed: HashSet::new(),
+            path: None,
+            done: false,
+            open: BinaryHeap::from([Reverse((manhattan(tiles, start, goal), 0, start))]),
+            came_from: HashMap::new(),
+            cost: HashMap::from([(start, 0)]),
+        }
+    }
+
+    // Tiles waiting to be expanded
+    pub fn frontier(&self) -> Vec<(usize, usize)> {
+        self.open
+            .iter()
+            .map(|&Reverse((_, _, pos))| pos)
+            .filter(|pos| !self.closed.contains(pos))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    // Expand the most promising tile, returns false once the search is over
+    pub fn step(&mut self, tiles: &Vec2d<Tile>) -> bool {
+        if self.done {
+            return false;
+        }
+        // Skip stale heap entries
+        let (g, current) = loop {
+            match self.open.pop() {
+                Some(Reverse((_, g, current))) if g <= self.cost[&current] => break (g, current),
+                Some(_) => {}
+                None => {
+                    self.done = true;
+                    return false;
+                }
+            }
+        };
+        self.closed.insert(current);
+
+        if current == self.goal {
+            let mut path = vec![current];
+            while let Some(&prev) = self.came_from.get(path.last().unwrap()) {
+                path.push(prev);
+            }
+            path.reverse();
+            self.path = Some(path);
+            self.done = true;
+            return false;
+        }
+
+        for next in open_neighbors(tiles, current) {
+            let next_cost = g + 1;
+            if self.cost.get(&next).is_none_or(|&c| next_cost < c) {
+                self.cost.insert(next, next_cost);
+                self.came_from.insert(next, current);
+                self.open.push(Reverse((
+                    next_cost + manhattan(tiles, next, self.goal),
+                    next_cost,
+                    next,
+                )));
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{
+        Algorithm, blank_tiles, carve, internal_walls, mark_exit, remove_n_walls_with,
+        remove_walls_between_positions, restore_walls, snapshot_walls,
+    };
+    use crate::tile::Wall;
+    use macroquad::rand::RandGenerator;
+
+    // 2x2 with all four inner walls open, a single loop
+    fn open_square() -> Vec2d<Tile> {
+        let mut tiles = blank_tiles(2, 2);
+        for (a, b) in [
+            ((0, 0), (1, 0)),
+            ((0, 0), (0, 1)),
+            ((1, 0), (1, 1)),
+            ((0, 1), (1, 1)),
+        ] {
+            remove_walls_between_positions(&mut tiles, a, b);
+        }
+        tiles
+    }
+
+    #[test]
+    fn diamond_has_two_shortest_paths() {
+        assert_eq!(count_shortest_paths(&open_square(), (0, 0), (1, 1)), 2);
+        assert_eq!(count_shortest_paths(&open_square(), (0, 0), (1, 0)), 1);
+    }
+
+    #[test]
+    fn perfect_maze_has_one_shortest_path() {
+        macroquad::rand::srand(7);
+        let mut tiles = blank_tiles(8, 8);
+        carve(&mut tiles, Algorithm::default());
+        assert_eq!(count_shortest_paths(&tiles, (0, 0), (7, 7)), 1);
+    }
+
+    #[test]
+    fn longest_path_spans_the_tree() {
+        // A corridor along the top row with a one tile branch down from the middle
+        let mut tiles = blank_tiles(5, 2);
+        for col in 1..5 {
+            remove_walls_between_positions(&mut tiles, (col - 1, 0), (col, 0));
+        }
+        remove_walls_between_positions(&mut tiles, (2, 0), (2, 1));
+        let path = longest_path(&tiles);
+        assert_eq!(path.len(), 5);
+        let mut ends = [path[0], path[4]];
+        ends.sort();
+        assert_eq!(ends, [(0, 0), (4, 0)]);
+    }
+
+    #[test]
+    fn unreachable_exit_has_no_paths() {
+        assert_eq!(count_shortest_paths(&blank_tiles(2, 1), (0, 0), (1, 0)), 0);
+    }
+
+    #[test]
+    fn dijkstra_on_uniform_weights_matches_bfs() {
+        macroquad::rand::srand(11);
+        let mut tiles = blank_tiles(8, 8);
+        carve(&mut tiles, Algorithm::default());
+        let rng = RandGenerator::new();
+        rng.srand(11);
+        remove_n_walls_with(&mut tiles, 10, &rng);
+        let distances = compute_distances(&tiles, (0, 0));
+        for goal in [(7, 7), (3, 5), (0, 7), (7, 0)] {
+            let path = solve_dijkstra(&tiles, (0, 0), goal).unwrap();
+            let steps = distances.index(goal.0, goal.1).unwrap();
+            assert_eq!(path.len() - 1, steps);
+            assert_eq!(path_weight(&tiles, &path), steps as f32);
+        }
+    }
+
+    #[test]
+    fn dijkstra_prefers_the_cheaper_route() {
+        // Fully open 3x2, mud on the direct route along the top row
+        let mut tiles = blank_tiles(3, 2);
+        for (a, b) in internal_walls(&tiles) {
+            remove_walls_between_positions(&mut tiles, a, b);
+        }
+        tiles.index_mut(1, 0).weight = 10.0;
+        let path = solve_dijkstra(&tiles, (0, 0), (2, 0)).unwrap();
+        assert_eq!(path, vec![(0, 0), (0, 1), (1, 1), (2, 1), (2, 0)]);
+        assert_eq!(path_weight(&tiles, &path), 4.0);
+        let direct = solve_astar(&tiles, (0, 0), (2, 0)).unwrap();
+        assert_eq!(path_weight(&tiles, &direct), 11.0);
+    }
+
+    #[test]
+    fn solution_cache_picks_up_every_wall_change() {
+        // A U from (0, 0) around to (1, 0), three steps
+        let mut tiles = blank_tiles(2, 2);
+        remove_walls_between_positions(&mut tiles, (0, 0), (0, 1));
+        remove_walls_between_positions(&mut tiles, (0, 1), (1, 1));
+        remove_walls_between_positions(&mut tiles, (1, 1), (1, 0));
+        let u_walls = snapshot_walls(&tiles);
+        let mut cache = SolutionCache::new();
+        let solved = solve_astar(&tiles, (0, 0), (1, 0)).unwrap().len() - 1;
+        assert_eq!(solved, 3);
+        assert_eq!(cache.solution_len(&tiles, (0, 0), (1, 0)), Some(solved));
+        assert_eq!(cache.solution_len(&tiles, (0, 0), (1, 0)), Some(solved));
+
+        // The lowest level wall setters invalidate it too
+        tiles.set_wall((0, 0), Wall::Right, false);
+        tiles.set_wall((1, 0), Wall::Left, false);
+        assert_eq!(cache.solution_len(&tiles, (0, 0), (1, 0)), Some(1));
+        tiles.set_walls_from_mask((0, 0), 0x7);
+        tiles.set_wall((1, 0), Wall::Left, true);
+        assert_eq!(cache.solution_len(&tiles, (0, 0), (1, 0)), Some(3));
+        tiles.set_wall((0, 1), Wall::Right, true);
+        tiles.set_wall((1, 1), Wall::Left, true);
+        assert_eq!(cache.solution_len(&tiles, (0, 0), (1, 0)), None);
+
+        restore_walls(&mut tiles, &u_walls);
+        assert_eq!(cache.solution_len(&tiles, (0, 0), (1, 0)), Some(3));
+        remove_walls_between_positions(&mut tiles, (0, 0), (1, 0));
+        assert_eq!(cache.solution_len(&tiles, (0, 0), (1, 0)), Some(1));
+        assert_eq!(cache.solution_len(&tiles, (0, 0), (1, 1)), Some(2));
+    }
+
+    #[test]
+    fn solvable_maze_is_playable() {
+        macroquad::rand::srand(3);
+        let mut tiles = blank_tiles(5, 5);
+        carve(&mut tiles, Algorithm::default());
+        mark_exit(&mut tiles, (4, 4));
+        assert_eq!(validate_playable(&tiles), Ok(()));
+    }
+
+    #[test]
+    fn walled_off_exit_is_refused() {
+        let mut tiles = open_square();
+        assert_eq!(validate_playable(&tiles), Err(PlayError::NoExit));
+        let mut exit_room = blank_tiles(3, 1);
+        remove_walls_between_positions(&mut exit_room, (0, 0), (1, 0));
+        mark_exit(&mut exit_room, (2, 0));
+        assert_eq!(
+            validate_playable(&exit_room),
+            Err(PlayError::Unreachable {
+                entrance: (0, 0),
+                exit: (2, 0)
+            })
+        );
+        mark_exit(&mut tiles, (1, 1));
+        assert_eq!(validate_playable(&tiles), Ok(()));
+    }
+
+    #[test]
+    fn practice_guide_runs_from_the_player_to_the_exit() {
+        macroquad::rand::srand(3);
+        let mut tiles = blank_tiles(6, 6);
+        carve(&mut tiles, Algorithm::default());
+        let guide = practice_guide(&tiles, (2, 3), (5, 5));
+        assert!(!guide.is_empty());
+        assert_eq!(guide.first(), Some(&(2, 3)));
+        assert_eq!(guide.last(), Some(&(5, 5)));
+        // Walled off, nothing to show
+        assert!(practice_guide(&blank_tiles(2, 1), (0, 0), (1, 0)).is_empty());
+    }
+
+    #[test]
+    fn path_directions_map_each_step_to_a_cardinal_move() {
+        let mut tiles = blank_tiles(3, 3);
+        let path = [(0, 0), (1, 0), (1, 1), (0, 1), (0, 0)];
+        assert_eq!(
+            path_directions(&tiles, &path),
+            vec![
+                Direction::Right,
+                Direction::Down,
+                Direction::Left,
+                Direction::Up
+            ]
+        );
+        // A jump ends the moves
+        assert_eq!(
+            path_directions(&tiles, &[(0, 0), (1, 0), (2, 2), (2, 1)]),
+            vec![Direction::Right]
+        );
+        // Across the edge of a wrapping maze
+        tiles.wrap = true;
+        assert_eq!(
+            path_directions(&tiles, &[(0, 0), (2, 0)]),
+            vec![Direction::Left]
+        );
+    }
+
+    #[test]
+    fn unexplored_hint_leads_past_the_explored_tiles() {
+        let mut tiles = blank_tiles(6, 1);
+        for col in 1..6 {
+            remove_walls_between_positions(&mut tiles, (col - 1, 0), (col, 0));
+        }
+        let mut explored: HashSet<(usize, usize)> = (0..4).map(|col| (col, 0)).collect();
+        assert_eq!(
+            path_to_unexplored(&tiles, (1, 0), &explored),
+            Some(vec![(1, 0), (2, 0), (3, 0), (4, 0)])
+        );
+        // The exit is skipped
+        mark_exit(&mut tiles, (4, 0));
+        assert_eq!(
+            path_to_unexplored(&tiles, (1, 0), &explored).and_then(|path| path.last().copied()),
+            Some((5, 0))
+        );
+        explored.insert((5, 0));
+        assert_eq!(path_to_unexplored(&tiles, (1, 0), &explored), None);
+    }
+
+    #[test]
+    fn stepping_the_solver_reaches_the_goal() {
+        macroquad::rand::srand(11);
+        let mut tiles = blank_tiles(7, 5);
+        carve(&mut tiles, Algorithm::default());
+        let mut solver = SolverState::new(&tiles, (0, 0), (6, 4));
+        assert_eq!(solver.frontier(), vec![(0, 0)]);
+        let mut steps = 0;
+        while solver.step(&tiles) {
+            steps += 1;
+            assert!(steps < tiles.vec.len());
+            assert!(solver.path.is_none());
+        }
+        assert!(solver.done && solver.closed.contains(&(6, 4)));
+        assert_eq!(solver.path, solve_astar(&tiles, (0, 0), (6, 4)));
+        assert_eq!(
+            solver.path.as_ref().unwrap().len(),
+            steps_to_go(&tiles, (0, 0), (6, 4)).unwrap() + 1
+        );
+        assert!(!solver.step(&tiles));
+
+        // No way through, the search ends without a path
+        let mut walled = SolverState::new(&blank_tiles(2, 1), (0, 0), (1, 0));
+        while walled.step(&blank_tiles(2, 1)) {}
+        assert!(walled.done && walled.path.is_none());
+    }
+
+    #[test]
+    fn steps_to_go_drop_by_one_along_the_solution() {
+        macroquad::rand::srand(13);
+        let mut tiles = blank_tiles(8, 6);
+        carve(&mut tiles, Algorithm::default());
+        remove_n_walls_with(&mut tiles, 6, &RandGenerator::new());
+        let path = solve_astar(&tiles, (0, 0), (7, 5)).unwrap();
+        for (i, &pos) in path.iter().enumerate() {
+            assert_eq!(steps_to_go(&tiles, pos, (7, 5)), Some(path.len() - 1 - i));
+        }
+        assert_eq!(steps_to_go(&blank_tiles(2, 1), (0, 0), (1, 0)), None);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::ghost::Recording;
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    // Number of tile changes made by the player
+    pub steps: u32,
+    // Seconds since the maze became playable
+    pub elapsed: f32,
+    // Fastest competitive finish, kept across mazes
+    pub best_time: Option<f32>,
+    // Breadcrumbs, every tile the player has stood on in this maze
+    pub explored: HashSet<(usize, usize)>,
+    // Player 1's path through this maze, kept as a ghost after a win
+    pub run: Recording,
+    // Seconds since the player last moved onto another tile
+    pub idle: f32,
+    // Elapsed time at each checkpoint reached so far
+    pub splits: Vec<f32>,
+    last_tile: Option<(usize, usize)>,
+}
+
+// Fair countdown for a race: twice the time needed to walk the solution at
+// `tiles_per_second`, plus a few seconds to get going
+pub fn time_limit_for(solution_len: usize, tiles_per_second: f32) -> f32 {
+    5.0 + 2.0 * solution_len as f32 / tiles_per_second
+}
+
+// Share of the steps taken that were needed, as a percentage. Teleports can
+// beat the walking solution, so it never goes over 100
+pub fn efficiency(optimal: usize, steps: u32) -> f32 {
+    if steps == 0 {
+        return 100.0;
+    }
+    (optimal as f32 / steps as f32 * 100.0).min(100.0)
+}
+
+// Share of the maze's `total` tiles walked on, as a percentage. A maze of one
+// tile (or none) is all explored from the start
+pub fn explored_share(explored: usize, total: usize) -> f32 {
+    if total <= 1 {
+        return 100.0;
+    }
+    (explored as f32 / total as f32 * 100.0).min(100.0)
+}
+
+// A race is won only when the exit is reached with time remaining
+pub fn beat_the_clock(time_limit: Option<f32>, elapsed: f32) -> bool {
+    time_limit.is_none_or(|limit| elapsed < limit)
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn remaining(&self, time_limit: Option<f32>) -> Option<f32> {
+        time_limit.map(|limit| (limit - self.elapsed).max(0.0))
+    }
+
+    // Start counting for a new maze, the best time carries over
+    pub fn next_maze(&mut self) {
+        *self = Self {
+            best_time: self.best_time,
+            ..Self::default()
+        };
+    }
+
+    // Practice runs never count towards the best time
+    pub fn record_win(&mut self, practice: bool) {
+        if !practice && self.best_time.is_none_or(|best| self.elapsed < best) {
+            self.best_time = Some(self.elapsed);
+        }
+    }
+
+    // Record a split when `tile_pos` is the next checkpoint, reaching a later
+    // one first doesn't count
+    pub fn record_split(&mut self, checkpoints: &[(usize, usize)], tile_pos: (usize, usize)) {
+        if checkpoints.get(self.splits.len()) == Some(&tile_pos) {
+            self.splits.push(self.elapsed);
+        }
+    }
+
+    // No tile change for `threshold` seconds
+    pub fn is_stuck(&self, threshold: f32) -> bool {
+        self.idle >= threshold
+    }
+
+    // Advance the timer and count a step whenever the player enters a new tile
+    pub fn update(&mut self, dt: f32, tile_pos: (usize, usize)) {
+        self.elapsed += dt;
+        if let Some(last) = self.last_tile
+            && last != tile_pos
+        {
+            self.steps += 1;
+            self.idle = 0.0;
+        } else {
+            self.idle += dt;
+        }
+        self.last_tile = Some(tile_pos);
+        self.explored.insert(tile_pos);
+    }
+}
+
+// Seconds of frames averaged into one FPS reading
+const FPS_WINDOW: f32 = 0.5;
+
+// Left to sleep after a frame whose work took `elapsed` seconds so frames
+// come `target_fps` times a second, None when the frame already ran long
+pub fn frame_sleep(target_fps: u32, elapsed: f64) -> Option<Duration> {
+    let remaining = 1.0 / target_fps.max(1) as f64 - elapsed;
+    (remaining > 0.0).then(|| Duration::from_secs_f64(remaining))
+}
+
+// Frames per second averaged over a short window, steadier than 1 / dt
+#[derive(Debug, Clone, Default)]
+pub struct FpsCounter {
+    frames: u32,
+    time: f32,
+    pub fps: f32,
+}
+
+impl FpsCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.frames += 1;
+        self.time += dt;
+        if self.time >= FPS_WINDOW {
+            self.fps = self.frames as f32 / self.time;
+            self.frames = 0;
+            self.time = 0.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reaching_the_exit_in_time_wins() {
+        assert!(beat_the_clock(None, 1e6));
+        assert!(beat_the_clock(Some(30.0), 29.9));
+        assert!(!beat_the_clock(Some(30.0), 30.0));
+        assert!(!beat_the_clock(Some(30.0), 31.0));
+    }
+
+    #[test]
+    fn efficiency_is_the_share_of_needed_steps() {
+        assert_eq!(efficiency(10, 10), 100.0);
+        assert_eq!(efficiency(10, 40), 25.0);
+        // Teleports can beat the walk, and no steps means nothing wasted
+        assert_eq!(efficiency(10, 5), 100.0);
+        assert_eq!(efficiency(10, 0), 100.0);
+        assert_eq!(efficiency(0, 0), 100.0);
+    }
+
+    #[test]
+    fn explored_share_is_a_percentage_of_the_tiles() {
+        assert_eq!(explored_share(0, 40), 0.0);
+        assert_eq!(explored_share(10, 40), 25.0);
+        assert_eq!(explored_share(40, 40), 100.0);
+        assert_eq!(explored_share(50, 40), 100.0);
+        // Nothing or a single tile to explore is explored already
+        assert_eq!(explored_share(0, 0), 100.0);
+        assert_eq!(explored_share(1, 1), 100.0);
+    }
+
+    #[test]
+    fn frame_sleep_fills_the_rest_of_the_frame() {
+        assert_eq!(frame_sleep(50, 0.0), Some(Duration::from_millis(20)));
+        assert_eq!(
+            frame_sleep(50, 0.015),
+            Some(Duration::from_secs_f64(0.02 - 0.015))
+        );
+        // Long frames don't sleep, a cap of 0 counts as 1 fps
+        assert_eq!(frame_sleep(50, 0.02), None);
+        assert_eq!(frame_sleep(50, 0.5), None);
+        assert_eq!(frame_sleep(0, 0.0), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn stuck_after_idling_on_one_tile() {
+        let mut stats = Stats::new();
+        stats.update(0.5, (0, 0));
+        stats.update(0.5, (0, 0));
+        assert!(!stats.is_stuck(3.0));
+        stats.update(2.0, (0, 0));
+        assert!(stats.is_stuck(3.0));
+        // Moving on resets the idle time
+        stats.update(0.1, (1, 0));
+        assert!(!stats.is_stuck(3.0));
+        assert_eq!(stats.steps, 1);
+        assert!(stats.is_stuck(0.0));
+    }
+
+    #[test]
+    fn practice_runs_keep_the_best_time() {
+        let mut stats = Stats::new();
+        stats.elapsed = 20.0;
+        stats.record_win(true);
+        assert_eq!(stats.best_time, None);
+        stats.record_win(false);
+        stats.next_maze();
+        stats.elapsed = 10.0;
+        stats.record_win(true);
+        assert_eq!(stats.best_time, Some(20.0));
+    }
+}
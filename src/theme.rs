@@ -0,0 +1,195 @@
+use crate::tile::{ENTRANCE_COLOR, EXIT_COLOR, PATH_COLOR, WALL_COLOR};
+
+use macroquad::prelude::*;
+
+// Gap used when the "tile floor" look is switched on
+pub const TILE_FLOOR_GAP: f32 = 3.0;
+// Wall edge softness (in border widths) range and +/- step
+pub const MAX_SMOOTHNESS: f32 = 2.0;
+pub const SMOOTHNESS_STEP: f32 = 0.25;
+// Brightness and contrast multiplier range for the tile shader and F6-F9 step
+pub const MIN_LEVEL: f32 = 0.25;
+pub const MAX_LEVEL: f32 = 2.0;
+pub const LEVEL_STEP: f32 = 0.1;
+// Player colors cycled with K, none of them is the exit's yellow
+pub const PLAYER_COLORS: [Color; 4] = [SKYBLUE, LIME, PINK, WHITE];
+
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub background: Color,
+    pub wall_color: Color,
+    // Carved floor, uncarved tiles keep their own color
+    pub path_color: Color,
+    // Lattice drawn behind the maze
+    pub grid_color: Color,
+    // Pixels each tile is inset from its cell on walled sides
+    pub gap: f32,
+    // Wall edge softness, 0 draws hard pixel walls
+    pub smoothness: f32,
+    // Tile shader levels, 1 leaves the colors as they are
+    pub brightness: f32,
+    pub contrast: f32,
+    pub player_color: Color,
+    pub exit_color: Color,
+    pub entrance_color: Color,
+    // Outline the exit so it stands out without relying on hue
+    pub exit_marker: bool,
+    // Removed walls slide open instead of vanishing
+    pub animate_walls: bool,
+    // Tile the generator is carving from
+    pub carve_head_color: Color,
+    // Tiles the generator has already reached
+    pub visited_color: Color,
+    // Reached tiles still on the generator's stack
+    pub stack_color: Color,
+    // Barely visible, traps are meant to be a risk
+    pub trap_color: Color,
+    // Checkpoints still to reach
+    pub checkpoint_color: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            background: BLACK,
+            wall_color: WALL_COLOR,
+            path_color: PATH_COLOR,
+            grid_color: Color::new(1.0, 1.0, 1.0, 0.08),
+            gap: 0.0,
+            smoothness: 0.0,
+            brightness: 1.0,
+            contrast: 1.0,
+            player_color: PLAYER_COLORS[0],
+            exit_color: EXIT_COLOR,
+            entrance_color: ENTRANCE_COLOR,
+            exit_marker: false,
+            animate_walls: false,
+            carve_head_color: Color::new(0.0, 0.8, 1.0, 0.7),
+            visited_color: Color::new(0.2, 0.5, 1.0, 0.25),
+            stack_color: Color::new(1.0, 0.4, 0.8, 0.45),
+            trap_color: Color::new(0.0, 0.0, 0.0, 0.3),
+            checkpoint_color: Color::new(0.6, 0.9, 1.0, 0.8),
+        }
+    }
+}
+
+// Relative luminance from the Rec. 709 weights, 0 is black and 1 white
+pub fn luminance(color: Color) -> f32 {
+    0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b
+}
+
+impl Theme {
+    // Colorblind friendly palette, the floor, walls, entrance and exit are told
+    // apart by brightness alone and the exit also gets an outline
+    pub fn high_contrast() -> Self {
+        Self {
+            wall_color: WHITE,
+            path_color: Color::new(0.05, 0.05, 0.05, 1.0),
+            grid_color: Color::new(1.0, 1.0, 1.0, 0.15),
+            exit_color: Color::new(1.0, 0.5, 0.0, 1.0),
+            entrance_color: Color::new(0.0, 0.3, 0.0, 1.0),
+            exit_marker: true,
+            ..Self::default()
+        }
+    }
+
+    // Brightness and contrast kept within MIN_LEVEL..=MAX_LEVEL, as the
+    // shader gets them
+    pub fn levels(&self) -> (f32, f32) {
+        let clamp = |level: f32| {
+            if level.is_nan() {
+                1.0
+            } else {
+                level.clamp(MIN_LEVEL, MAX_LEVEL)
+            }
+        };
+        (clamp(self.brightness), clamp(self.contrast))
+    }
+
+    // Player 1 uses player_color, the others take the rest of the palette in order
+    pub fn player_color(&self, index: usize) -> Color {
+        if index == 0 {
+            return self.player_color;
+        }
+        PLAYER_COLORS
+            .into_iter()
+            .filter(|&color| color != self.player_color)
+            .cycle()
+            .nth(index - 1)
+            .unwrap()
+    }
+
+    // Move on to the next color in PLAYER_COLORS
+    pub fn next_player_color(&mut self) {
+        let current = PLAYER_COLORS
+            .iter()
+            .position(|&color| color == self.player_color)
+            .unwrap_or(0);
+        self.player_color = PLAYER_COLORS[(current + 1) % PLAYER_COLORS.len()];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn high_contrast_colors_differ_in_luminance() {
+        let theme = Theme::high_contrast();
+        let colors = [
+            theme.wall_color,
+            theme.path_color,
+            theme.exit_color,
+            theme.entrance_color,
+        ];
+        for (i, &a) in colors.iter().enumerate() {
+            for &b in &colors[i + 1..] {
+                let apart = (luminance(a) - luminance(b)).abs();
+                assert!(apart >= 0.15, "{:?} and {:?} are {} apart", a, b, apart);
+            }
+        }
+    }
+
+    #[test]
+    fn players_never_wear_the_exit_color() {
+        for mut theme in [Theme::default(), Theme::high_contrast()] {
+            for _ in 0..PLAYER_COLORS.len() {
+                for index in 0..4 {
+                    assert_ne!(theme.player_color(index), theme.exit_color);
+                }
+                theme.next_player_color();
+            }
+        }
+    }
+
+    #[test]
+    fn levels_are_clamped_for_the_shader() {
+        let levels = |brightness, contrast| {
+            Theme {
+                brightness,
+                contrast,
+                ..Theme::default()
+            }
+            .levels()
+        };
+        assert_eq!(Theme::default().levels(), (1.0, 1.0));
+        assert_eq!(levels(1.5, 0.5), (1.5, 0.5));
+        assert_eq!(levels(0.0, 10.0), (MIN_LEVEL, MAX_LEVEL));
+        assert_eq!(levels(-3.0, f32::INFINITY), (MIN_LEVEL, MAX_LEVEL));
+        assert_eq!(levels(f32::NAN, f32::NAN), (1.0, 1.0));
+    }
+
+    #[test]
+    fn carving_head_stands_out_from_the_maze() {
+        for theme in [Theme::default(), Theme::high_contrast()] {
+            for color in [
+                theme.path_color,
+                theme.wall_color,
+                theme.visited_color,
+                theme.stack_color,
+            ] {
+                assert_ne!(theme.carve_head_color, color);
+            }
+        }
+    }
+}
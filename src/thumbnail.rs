@@ -0,0 +1,75 @@
+use crate::tile::{ENTRANCE_COLOR, EXIT_COLOR, PATH_COLOR, Tile, WALL_COLOR, Wall};
+use crate::utils::Vec2d;
+
+use macroquad::prelude::*;
+
+// Small preview of a maze, `px` pixels per tile with one pixel wide walls,
+// drawn on the CPU so it works without a window. Images can't be wider or
+// taller than u16::MAX, so big mazes get fewer pixels per tile and past one
+// pixel the far tiles are cut off
+pub fn render_thumbnail(tiles: &Vec2d<Tile>, px: u16) -> Image {
+    let max_side = u16::MAX as u32;
+    let longest = tiles.cols.max(tiles.rows).max(1) as u32;
+    let px = (px.max(2) as u32).min(max_side / longest).max(1);
+    let width = (tiles.cols as u32 * px).min(max_side);
+    let height = (tiles.rows as u32 * px).min(max_side);
+    let mut image = Image::gen_image_color(width as u16, height as u16, PATH_COLOR);
+    for tile in &tiles.vec {
+        let x0 = tile.col as u32 * px;
+        let y0 = tile.row as u32 * px;
+        if x0 >= width || y0 >= height {
+            continue;
+        }
+        let last = px - 1;
+        for dy in 0..px {
+            for dx in 0..px {
+                let wall = (dx == 0 && tile.walls().contains(&Wall::Left))
+                    || (dy == 0 && tile.walls().contains(&Wall::Top))
+                    || (dx == last && tile.walls().contains(&Wall::Right))
+                    || (dy == last && tile.walls().contains(&Wall::Bottom));
+                let color = if wall {
+                    WALL_COLOR
+                } else if tile.exit {
+                    EXIT_COLOR
+                } else if tile.entrance {
+                    ENTRANCE_COLOR
+                } else {
+                    PATH_COLOR
+                };
+                image.set_pixel(x0 + dx, y0 + dy, color);
+            }
+        }
+    }
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{blank_tiles, mark_exit, remove_walls_between_positions};
+
+    fn rgba(color: Color) -> [u8; 4] {
+        color.into()
+    }
+
+    #[test]
+    fn walls_and_the_exit_get_their_colors() {
+        let mut tiles = blank_tiles(2, 1);
+        remove_walls_between_positions(&mut tiles, (0, 0), (1, 0));
+        mark_exit(&mut tiles, (1, 0));
+        let image = render_thumbnail(&tiles, 4);
+        assert_eq!((image.width, image.height), (8, 4));
+        // Left wall of the first tile, its opened right side, the exit inside
+        assert_eq!(rgba(image.get_pixel(0, 1)), rgba(WALL_COLOR));
+        assert_eq!(rgba(image.get_pixel(3, 1)), rgba(PATH_COLOR));
+        assert_eq!(rgba(image.get_pixel(5, 1)), rgba(EXIT_COLOR));
+    }
+
+    #[test]
+    fn big_mazes_shrink_to_fit() {
+        let image = render_thumbnail(&blank_tiles(3000, 1), 100);
+        assert_eq!((image.width, image.height), (63000, 21));
+        let image = render_thumbnail(&blank_tiles(70000, 1), 2);
+        assert_eq!((image.width, image.height), (u16::MAX, 1));
+    }
+}
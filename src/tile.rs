@@ -1,11 +1,20 @@
+use crate::theme::Theme;
+use crate::utils::Vec2d;
+
 use macroquad::prelude::*;
 
 use std::collections::HashSet;
 
 use std::hash::{Hash, Hasher};
 
-const PATH_COLOR: Color = BROWN;
+pub const PATH_COLOR: Color = BROWN;
 pub const WALL_COLOR: Color = DARKGRAY;
+pub const EXIT_COLOR: Color = YELLOW;
+pub const ENTRANCE_COLOR: Color = DARKGREEN;
+// Seconds a freshly carved tile takes to fade from its old color to the floor
+pub const CARVE_FADE_SECONDS: f32 = 0.3;
+// Seconds a removed wall takes to slide open with `--animate-walls`
+pub const WALL_OPEN_SECONDS: f32 = 0.25;
 
 #[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
 pub enum Wall {
@@ -15,16 +24,117 @@ pub enum Wall {
     Bottom = 8,
 }
 
+// Every side in mask bit order
+pub const WALLS: [Wall; 4] = [Wall::Left, Wall::Top, Wall::Right, Wall::Bottom];
+
 #[derive(Debug, Clone)]
 pub struct Tile {
     pub col: usize,
     pub row: usize,
-    pub walls: HashSet<Wall>,
+    // Only changed through the Vec2d<Tile> methods below, which bump the revision
+    walls: HashSet<Wall>,
     pub screen_position: Vec2,
     pub width: f32,
     pub height: f32,
     pub color: Color,
     pub exit: bool,
+    // Where the players spawn, opened to the outside like the exit
+    pub entrance: bool,
+    // Fade running since the tile was first carved
+    pub color_transition: Option<ColorTransition>,
+    // Walls removed less than WALL_OPEN_SECONDS ago
+    pub openings: Vec<WallOpening>,
+    // Cost of stepping onto the tile for solve_dijkstra, 1 is plain floor
+    pub weight: f32,
+}
+
+// A removed wall still drawn sliding open, `elapsed` out of WALL_OPEN_SECONDS
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WallOpening {
+    pub wall: Wall,
+    pub elapsed: f32,
+}
+
+impl WallOpening {
+    // 0 when just removed, 1 once fully open
+    pub fn progress(&self) -> f32 {
+        (self.elapsed / WALL_OPEN_SECONDS).min(1.0)
+    }
+}
+
+// Fade from `from` to the tile's current fill, `elapsed` out of CARVE_FADE_SECONDS
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorTransition {
+    pub from: Color,
+    pub elapsed: f32,
+}
+
+// Mix of `from` and `to`, `t` is clamped to 0..=1
+pub fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    Color::from_vec(from.to_vec().lerp(to.to_vec(), t))
+}
+
+// What is left of the border along `wall` of `cell` when it is `progress` open:
+// two strips, one from each corner, shrinking toward the corners
+pub fn opening_strips(cell: Rect, wall: Wall, progress: f32) -> [Rect; 2] {
+    let thickness = cell.w.min(cell.h) / 8.0;
+    let closed = (1.0 - progress.clamp(0.0, 1.0)) / 2.0;
+    let (w, h) = (cell.w * closed, cell.h * closed);
+    match wall {
+        Wall::Left | Wall::Right => {
+            let x = if wall == Wall::Left {
+                cell.x
+            } else {
+                cell.right() - thickness
+            };
+            [
+                Rect::new(x, cell.y, thickness, h),
+                Rect::new(x, cell.bottom() - h, thickness, h),
+            ]
+        }
+        Wall::Top | Wall::Bottom => {
+            let y = if wall == Wall::Top {
+                cell.y
+            } else {
+                cell.bottom() - thickness
+            };
+            [
+                Rect::new(cell.x, y, w, thickness),
+                Rect::new(cell.right() - w, y, w, thickness),
+            ]
+        }
+    }
+}
+
+// Shrink a cell by `gap` on each walled side, open sides stay flush with the neighbor
+pub fn inset_rect(cell: Rect, walls: &HashSet<Wall>, gap: f32) -> Rect {
+    let gap = gap.clamp(0.0, cell.w.min(cell.h) / 4.0);
+    let side = |wall| if walls.contains(&wall) { gap } else { 0.0 };
+    let (left, top) = (side(Wall::Left), side(Wall::Top));
+    let (right, bottom) = (side(Wall::Right), side(Wall::Bottom));
+    Rect::new(
+        cell.x + left,
+        cell.y + top,
+        cell.w - left - right,
+        cell.h - top - bottom,
+    )
+}
+
+// Segment along each walled side of `cell`, in Wall order
+pub fn wall_lines(cell: Rect, walls: &HashSet<Wall>) -> Vec<(Vec2, Vec2)> {
+    let (top_left, top_right) = (cell.point(), vec2(cell.right(), cell.y));
+    let (bottom_left, bottom_right) = (vec2(cell.x, cell.bottom()), cell.point() + cell.size());
+    [
+        (Wall::Left, top_left, bottom_left),
+        (Wall::Top, top_left, top_right),
+        (Wall::Right, top_right, bottom_right),
+        (Wall::Bottom, bottom_left, bottom_right),
+    ]
+    .into_iter()
+    .filter(|(wall, _, _)| walls.contains(wall))
+    .map(|(_, from, to)| (from, to))
+    .collect()
 }
 
 impl PartialEq for Tile {
@@ -37,7 +147,7 @@ impl Eq for Tile {}
 
 impl Hash for Tile {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        (self.row + 1 * self.col + 1).hash(state);
+        (self.col, self.row).hash(state);
     }
 }
 
@@ -65,31 +175,260 @@ impl Tile {
             height,
             color,
             exit: false,
+            entrance: false,
+            color_transition: None,
+            openings: Vec::new(),
+            weight: 1.0,
         }
     }
 
-    pub fn remove_wall(&mut self, wall: &Wall) -> bool {
+    pub fn walls(&self) -> &HashSet<Wall> {
+        &self.walls
+    }
+
+    fn remove_wall(&mut self, wall: &Wall) -> bool {
+        if self.color != PATH_COLOR {
+            self.color_transition = Some(ColorTransition {
+                from: self.color,
+                elapsed: 0.0,
+            });
+        }
         self.color = PATH_COLOR;
-        self.walls.remove(wall)
+        let removed = self.walls.remove(wall);
+        if removed {
+            self.openings.push(WallOpening {
+                wall: *wall,
+                elapsed: 0.0,
+            });
+        }
+        removed
     }
 
-    pub fn draw(&self, material: &Material) {
-        let mut walls_sum: i32 = 0;
-        for wall in &self.walls {
-            walls_sum = walls_sum + (*wall as i32);
+    // Sum of the present walls (Left=1, Top=2, Right=4, Bottom=8), the mask the
+    // shader and every maze format use
+    pub fn wall_mask(&self) -> u8 {
+        self.walls.iter().map(|&wall| wall as u8).sum()
+    }
+
+    // Inverse of wall_mask, leaves the color alone. Bits above 0xf are ignored
+    // and the walls are set at once, without openings
+    fn set_walls_from_mask(&mut self, mask: u8) {
+        self.openings.clear();
+        self.walls = WALLS
+            .into_iter()
+            .filter(|&wall| mask & wall as u8 != 0)
+            .collect();
+    }
+
+    // Advance the carve fade and wall openings, dropping them once done
+    pub fn update_transition(&mut self, dt: f32) {
+        if let Some(transition) = &mut self.color_transition {
+            transition.elapsed += dt;
+            if transition.elapsed >= CARVE_FADE_SECONDS {
+                self.color_transition = None;
+            }
         }
-        let pixels: f32 = 8.0;
-        material.set_uniform("pixels", pixels);
-        material.set_uniform("border_side", walls_sum);
-        material.set_uniform("tile_color", self.color.to_vec());
-        material.set_uniform("border_color", WALL_COLOR.to_vec());
-        gl_use_material(&material);
-        draw_rectangle(
+        for opening in &mut self.openings {
+            opening.elapsed += dt;
+        }
+        self.openings.retain(|opening| opening.progress() < 1.0);
+    }
+
+    fn fill_color(&self, theme: &Theme) -> Color {
+        let color = self.settled_color(theme);
+        match self.color_transition {
+            // Uncarved tiles are wall colored, fade from the theme's walls
+            Some(transition) => {
+                let from = if transition.from == WALL_COLOR {
+                    theme.wall_color
+                } else {
+                    transition.from
+                };
+                lerp_color(from, color, transition.elapsed / CARVE_FADE_SECONDS)
+            }
+            None => color,
+        }
+    }
+
+    fn settled_color(&self, theme: &Theme) -> Color {
+        if self.exit {
+            theme.exit_color
+        } else if self.entrance {
+            theme.entrance_color
+        } else if self.color == PATH_COLOR {
+            theme.path_color
+        } else {
+            self.color
+        }
+    }
+
+    fn cell(&self) -> Rect {
+        Rect::new(
             self.screen_position.x,
             self.screen_position.y,
             self.width,
             self.height,
-            self.color,
+        )
+    }
+
+    pub fn draw(&self, material: &Material, theme: &Theme) {
+        let color = self.fill_color(theme);
+        let pixels: f32 = 8.0;
+        material.set_uniform("pixels", pixels);
+        material.set_uniform("border_side", self.wall_mask() as i32);
+        material.set_uniform("tile_color", color.to_vec());
+        material.set_uniform("border_color", theme.wall_color.to_vec());
+        material.set_uniform("smoothness", theme.smoothness);
+        let (brightness, contrast) = theme.levels();
+        material.set_uniform("brightness", brightness);
+        material.set_uniform("contrast", contrast);
+        gl_use_material(material);
+        // Only the drawn rectangle is inset, layout and collision keep the full cell
+        let rect = inset_rect(self.cell(), &self.walls, theme.gap);
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+    }
+
+    // The walls still sliding open, drawn over the tile without the shader
+    pub fn draw_openings(&self, theme: &Theme) {
+        for opening in &self.openings {
+            for strip in opening_strips(self.cell(), opening.wall, opening.progress()) {
+                draw_rectangle(strip.x, strip.y, strip.w, strip.h, theme.wall_color);
+            }
+        }
+    }
+
+    // Same look without the border shader, for when it fails to compile
+    pub fn draw_plain(&self, theme: &Theme) {
+        let rect = inset_rect(self.cell(), &self.walls, theme.gap);
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, self.fill_color(theme));
+        let thickness = (self.width / 8.0).max(1.0);
+        for (from, to) in wall_lines(self.cell(), &self.walls) {
+            draw_line(from.x, from.y, to.x, to.y, thickness, theme.wall_color);
+        }
+    }
+}
+
+// Wall changes go through the grid so each one bumps its revision, which the
+// solution cache and the minimap compare to spot a changed maze
+impl Vec2d<Tile> {
+    // Carve one side of the tile at `pos` away, flooring the tile and sliding
+    // the wall open. False when it was already gone
+    pub fn remove_wall(&mut self, pos: (usize, usize), wall: Wall) -> bool {
+        let removed = self.index_mut(pos.0, pos.1).remove_wall(&wall);
+        self.touch();
+        removed
+    }
+
+    // Put up or take down one side of the tile at `pos` as is, without a fade
+    // or an opening
+    pub fn set_wall(&mut self, pos: (usize, usize), wall: Wall, present: bool) {
+        let walls = &mut self.index_mut(pos.0, pos.1).walls;
+        if present {
+            walls.insert(wall);
+        } else {
+            walls.remove(&wall);
+        }
+        self.touch();
+    }
+
+    pub fn set_walls_from_mask(&mut self, pos: (usize, usize), mask: u8) {
+        self.index_mut(pos.0, pos.1).set_walls_from_mask(mask);
+        self.touch();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wall_mask_round_trips_all_sixteen_masks() {
+        let mut tile = Tile::new(0, 0, 0.0, 0.0, 1.0, 1.0, WALL_COLOR);
+        assert_eq!(tile.wall_mask(), 0xf);
+        for mask in 0..16 {
+            tile.set_walls_from_mask(mask);
+            assert_eq!(tile.wall_mask(), mask);
+            assert_eq!(tile.walls.len(), mask.count_ones() as usize);
+        }
+    }
+
+    #[test]
+    fn high_bits_are_ignored() {
+        let mut tile = Tile::new(0, 0, 0.0, 0.0, 1.0, 1.0, WALL_COLOR);
+        tile.set_walls_from_mask(0xf5);
+        assert_eq!(tile.wall_mask(), 0x5);
+        assert!(tile.walls.contains(&Wall::Left) && tile.walls.contains(&Wall::Right));
+    }
+
+    #[test]
+    fn inset_only_shrinks_walled_sides() {
+        let cell = Rect::new(10.0, 20.0, 40.0, 40.0);
+        let walls: HashSet<Wall> = [Wall::Left, Wall::Bottom].into_iter().collect();
+        assert_eq!(
+            inset_rect(cell, &walls, 2.0),
+            Rect::new(12.0, 20.0, 38.0, 38.0)
+        );
+        assert_eq!(inset_rect(cell, &HashSet::new(), 2.0), cell);
+        // The gap never eats more than a quarter of the cell per side
+        let all: HashSet<Wall> = WALLS.into_iter().collect();
+        assert_eq!(
+            inset_rect(cell, &all, 100.0),
+            Rect::new(20.0, 30.0, 20.0, 20.0)
         );
     }
+
+    #[test]
+    fn plain_wall_lines_follow_the_wall_set() {
+        let cell = Rect::new(0.0, 0.0, 10.0, 10.0);
+        let walls: HashSet<Wall> = [Wall::Bottom, Wall::Top].into_iter().collect();
+        assert_eq!(
+            wall_lines(cell, &walls),
+            vec![
+                (vec2(0.0, 0.0), vec2(10.0, 0.0)),
+                (vec2(0.0, 10.0), vec2(10.0, 10.0)),
+            ]
+        );
+        assert!(wall_lines(cell, &HashSet::new()).is_empty());
+        let all: HashSet<Wall> = WALLS.into_iter().collect();
+        assert_eq!(wall_lines(cell, &all)[0], (vec2(0.0, 0.0), vec2(0.0, 10.0)));
+        assert_eq!(wall_lines(cell, &all).len(), 4);
+    }
+
+    #[test]
+    fn carve_fade_reaches_the_path_color() {
+        assert_eq!(lerp_color(WALL_COLOR, PATH_COLOR, 0.0), WALL_COLOR);
+        assert_eq!(lerp_color(WALL_COLOR, PATH_COLOR, 1.0), PATH_COLOR);
+        // Past the end it stays on the target
+        assert_eq!(lerp_color(WALL_COLOR, PATH_COLOR, 2.0), PATH_COLOR);
+        let half = lerp_color(BLACK, WHITE, 0.5);
+        assert_eq!(half, Color::new(0.5, 0.5, 0.5, 1.0));
+
+        let mut tile = Tile::new(0, 0, 0.0, 0.0, 1.0, 1.0, WALL_COLOR);
+        tile.remove_wall(&Wall::Left);
+        assert_eq!(tile.color, PATH_COLOR);
+        tile.update_transition(CARVE_FADE_SECONDS / 2.0);
+        assert!(tile.color_transition.is_some());
+        tile.update_transition(CARVE_FADE_SECONDS / 2.0);
+        assert_eq!(tile.color_transition, None);
+    }
+
+    #[test]
+    fn wall_opening_is_fully_open_after_its_duration() {
+        let opening = |elapsed| WallOpening {
+            wall: Wall::Top,
+            elapsed,
+        };
+        assert_eq!(opening(0.0).progress(), 0.0);
+        assert_eq!(opening(WALL_OPEN_SECONDS / 2.0).progress(), 0.5);
+        assert_eq!(opening(WALL_OPEN_SECONDS).progress(), 1.0);
+        assert_eq!(opening(WALL_OPEN_SECONDS * 3.0).progress(), 1.0);
+
+        let mut tile = Tile::new(0, 0, 0.0, 0.0, 1.0, 1.0, WALL_COLOR);
+        tile.remove_wall(&Wall::Top);
+        assert_eq!(tile.openings, vec![opening(0.0)]);
+        tile.update_transition(WALL_OPEN_SECONDS / 2.0);
+        assert_eq!(tile.openings.len(), 1);
+        tile.update_transition(WALL_OPEN_SECONDS / 2.0);
+        assert!(tile.openings.is_empty());
+    }
 }
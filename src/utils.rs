@@ -1,16 +1,73 @@
 use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Source of Vec2d revisions, unique across every grid
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(0);
 
 #[derive(Debug, Clone)]
 pub struct Vec2d<T> {
     pub vec: Vec<T>,
     pub rows: usize,
     pub cols: usize,
+    // Edges connect around to the opposite side (toroidal mazes)
+    pub wrap: bool,
+    // Changes with every touch, so two equal revisions mean the same contents.
+    // Tile walls can only change through the Vec2d<Tile> methods, which touch
+    pub revision: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Vec2dError {
+    LengthMismatch {
+        len: usize,
+        rows: usize,
+        cols: usize,
+    },
+}
+
+impl fmt::Display for Vec2dError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Vec2dError::LengthMismatch { len, rows, cols } => write!(
+                f,
+                "expected {} x {} = {} elements, got {}",
+                rows,
+                cols,
+                rows * cols,
+                len
+            ),
+        }
+    }
 }
 
+impl std::error::Error for Vec2dError {}
+
 impl<T> Vec2d<T> {
+    // Panics on a length mismatch, loaders should use try_new
     pub fn new(vec: Vec<T>, rows: usize, cols: usize) -> Self {
-        assert!(vec.len() == rows * cols);
-        Self { vec, rows, cols }
+        Self::try_new(vec, rows, cols).unwrap()
+    }
+
+    pub fn try_new(vec: Vec<T>, rows: usize, cols: usize) -> Result<Self, Vec2dError> {
+        if vec.len() != rows * cols {
+            return Err(Vec2dError::LengthMismatch {
+                len: vec.len(),
+                rows,
+                cols,
+            });
+        }
+        Ok(Self {
+            vec,
+            rows,
+            cols,
+            wrap: false,
+            revision: NEXT_REVISION.fetch_add(1, Ordering::Relaxed),
+        })
+    }
+
+    // Mark the contents as changed
+    pub fn touch(&mut self) {
+        self.revision = NEXT_REVISION.fetch_add(1, Ordering::Relaxed);
     }
 
     pub fn row(&self, row: usize) -> &[T] {
@@ -18,6 +75,14 @@ impl<T> Vec2d<T> {
         &self.vec[i..(i + self.cols)]
     }
 
+    pub fn col(&self, col: usize) -> Vec<&T> {
+        self.vec.iter().skip(col).step_by(self.cols).collect()
+    }
+
+    pub fn rows_iter(&self) -> impl Iterator<Item = &[T]> {
+        self.vec.chunks(self.cols)
+    }
+
     pub fn index(&self, col: usize, row: usize) -> &T {
         let i = self.cols * row;
         &self.vec[i + col]
@@ -27,17 +92,115 @@ impl<T> Vec2d<T> {
         let i = self.cols * row;
         &mut self.vec[i + col]
     }
+
+    // (col, row) of the element at flat index i
+    pub fn coords(&self, i: usize) -> (usize, usize) {
+        (i % self.cols, i / self.cols)
+    }
+}
+
+impl<T: Clone> Vec2d<T> {
+    // Swap rows and columns, element (col, row) moves to (row, col)
+    pub fn transpose(&self) -> Vec2d<T> {
+        let mut vec = Vec::with_capacity(self.vec.len());
+        for col in 0..self.cols {
+            vec.extend(self.col(col).into_iter().cloned());
+        }
+        let mut transposed = Vec2d::new(vec, self.cols, self.rows);
+        transposed.wrap = self.wrap;
+        transposed
+    }
 }
 
 impl<T: std::fmt::Debug> std::fmt::Display for Vec2d<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut str = String::new();
-        for i in 0..self.rows {
+        for (i, row) in self.rows_iter().enumerate() {
             if i != 0 {
                 str.push_str(", ");
             }
-            str.push_str(&format!("{:?}", &self.row(i)));
+            str.push_str(&format!("{:?}", row));
         }
         write!(f, "[{}]", str)
     }
 }
+
+// Disjoint sets over 0..len with path halving
+#[derive(Debug, Clone)]
+pub struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    pub fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+        }
+    }
+
+    pub fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    // Returns false if a and b were already in the same set
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        self.parent[a] = b;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_new_accepts_a_matching_length() {
+        let grid = Vec2d::try_new(vec![1, 2, 3, 4, 5, 6], 2, 3).unwrap();
+        assert_eq!((grid.rows, grid.cols), (2, 3));
+        assert_eq!(grid.row(1), &[4, 5, 6]);
+        assert_eq!(*grid.index(2, 0), 3);
+    }
+
+    #[test]
+    fn col_picks_one_element_per_row() {
+        let grid = Vec2d::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        assert_eq!(grid.col(1), vec![&2, &5]);
+        assert_eq!(grid.col(2), vec![&3, &6]);
+        assert_eq!(
+            grid.rows_iter().collect::<Vec<_>>(),
+            vec![&[1, 2, 3], &[4, 5, 6]]
+        );
+    }
+
+    #[test]
+    fn transpose_twice_is_the_original() {
+        let grid = Vec2d::new(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let transposed = grid.transpose();
+        assert_eq!((transposed.rows, transposed.cols), (3, 2));
+        assert_eq!(transposed.vec, vec![1, 4, 2, 5, 3, 6]);
+        let back = transposed.transpose();
+        assert_eq!((back.rows, back.cols), (2, 3));
+        assert_eq!(back.vec, grid.vec);
+    }
+
+    #[test]
+    fn try_new_reports_a_length_mismatch() {
+        let err = Vec2d::try_new(vec![1, 2, 3], 2, 2).unwrap_err();
+        assert_eq!(
+            err,
+            Vec2dError::LengthMismatch {
+                len: 3,
+                rows: 2,
+                cols: 2
+            }
+        );
+    }
+}